@@ -0,0 +1,45 @@
+use crate::counting_allocator::allocations;
+use crate::midi::fixtures::{start_time, SECOND};
+use crate::state::{EditorState, GlissParam};
+
+pub const SAMPLE_RATE: f32 = 48_000.0;
+pub const BLOCK_SIZE: usize = 64;
+
+// what the scenario needs from a plugin format, each format's tests drive it through its own entry points
+pub trait PluginFormat {
+    fn state(&self) -> &EditorState;
+    // the way the format's host sets a param, plain values
+    fn set_param(&mut self, param: GlissParam, plain: f64);
+    // note ons delivered with the next block, the adapter's buffers must already have room for them
+    fn send_notes(&mut self, midi_numbers: &[u8]);
+    // one block of BLOCK_SIZE samples at now, returns the number of midi events sent
+    fn process(&mut self, now: f64) -> usize;
+    // the first audio output of the last block
+    fn output(&self) -> &[f32];
+}
+
+// a ui gesture and a chord, captured, sent to the benders, bent and previewed without allocating
+pub fn bends_without_allocating(format: &mut impl PluginFormat) {
+    // a simulated clock, each block moves it on by BLOCK_SIZE samples
+    let block = BLOCK_SIZE as f64 / SAMPLE_RATE as f64 * SECOND;
+    let mut now = start_time();
+    // Sine, so the preview synth renders
+    format.set_param(GlissParam::PreviewWaveform, 1.0);
+    format.process(now);
+
+    // a long bend keeps every voice sending pitch bends
+    format.state().set_parameter(GlissParam::HoldDuration, 5.0);
+    format.state().end_gesture();
+
+    let before = allocations();
+    format.send_notes(&[60, 64, 67]);
+    let mut sent = 0;
+    for _ in 0..200 {
+        now += block;
+        sent += format.process(now);
+    }
+    assert_eq!(allocations() - before, 0);
+    assert!(sent > 0);
+    assert!(format.state().update_engine_view().chords[0].sent_to_bender);
+    assert!(format.output().iter().any(|sample| *sample != 0.0));
+}
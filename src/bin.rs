@@ -6,17 +6,38 @@
 extern crate lazy_static;
 
 mod draw;
+mod engine;
+mod lv2;
 mod midi;
+mod render;
 mod state;
+mod synth;
 mod ui;
 
+#[cfg(test)]
+mod audio_scenario;
+#[cfg(test)]
+mod counting_allocator;
+
+use engine::Engine;
 use state::EditorState;
+use synth::{PreviewSynth, RENDER_CAPACITY};
 
+use std::path::Path;
 use std::sync::Arc;
+use std::thread;
 use std::time::{Duration, SystemTime};
 
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, OutputCallbackInfo, Sample, SampleFormat, Stream, StreamConfig};
 use egui::CtxRef;
 use egui_baseview::{EguiWindow, Queue};
+#[cfg(unix)]
+use midir::os::unix::{VirtualInput, VirtualOutput};
+use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+use rtrb::{Consumer, Producer, RingBuffer};
+use vst::event::MidiEvent;
 
 lazy_static! {
     pub static ref GLISS_EPOCH: Duration = SystemTime::now()
@@ -28,13 +49,214 @@ lazy_static! {
 //pub const PITCH_BEND_RANGE: u8 = 48;
 //pub const PITCH_BEND_RANGE: u8 = 24;
 
+// stands in for the host's process calls
+const BEND_INTERVAL: Duration = Duration::from_millis(1);
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as f64
+}
+
+// incoming midi waiting for the bend thread, which owns the engine
+const INPUT_QUEUE_CAPACITY: usize = 1024;
+// what the benders sent, waiting for the audio callback's preview synth
+const PREVIEW_QUEUE_CAPACITY: usize = 1024;
+
+const IN_PORT: &str = "DeepGliss In";
+const OUT_PORT: &str = "DeepGliss Out";
+
+// virtual ports to patch between a keyboard and a synth
+#[cfg(unix)]
+fn open_ports(
+    on_message: impl FnMut(u64, &[u8], &mut ()) + Send + 'static,
+) -> Result<(MidiInputConnection<()>, MidiOutputConnection)> {
+    let input = MidiInput::new("DeepGliss")?
+        .create_virtual(IN_PORT, on_message, ())
+        .map_err(|e| anyhow!("failed to create {IN_PORT}: {e}"))?;
+    let output = MidiOutput::new("DeepGliss")?
+        .create_virtual(OUT_PORT)
+        .map_err(|e| anyhow!("failed to create {OUT_PORT}: {e}"))?;
+    Ok((input, output))
+}
+
+// without virtual ports, connect to loopback ports named DeepGliss In and DeepGliss Out
+#[cfg(not(unix))]
+fn open_ports(
+    on_message: impl FnMut(u64, &[u8], &mut ()) + Send + 'static,
+) -> Result<(MidiInputConnection<()>, MidiOutputConnection)> {
+    let input = MidiInput::new("DeepGliss")?;
+    let in_port = input
+        .ports()
+        .into_iter()
+        .find(|port| {
+            input
+                .port_name(port)
+                .is_ok_and(|name| name.contains(IN_PORT))
+        })
+        .ok_or_else(|| anyhow!("no midi input named {IN_PORT}"))?;
+    let input = input
+        .connect(&in_port, IN_PORT, on_message, ())
+        .map_err(|e| anyhow!("failed to connect to {IN_PORT}: {e}"))?;
+    let output = MidiOutput::new("DeepGliss")?;
+    let out_port = output
+        .ports()
+        .into_iter()
+        .find(|port| {
+            output
+                .port_name(port)
+                .is_ok_and(|name| name.contains(OUT_PORT))
+        })
+        .ok_or_else(|| anyhow!("no midi output named {OUT_PORT}"))?;
+    let output = output
+        .connect(&out_port, OUT_PORT)
+        .map_err(|e| anyhow!("failed to connect to {OUT_PORT}: {e}"))?;
+    Ok((input, output))
+}
+
+// the input closes when dropped
+fn connect_midi(
+    state: &Arc<EditorState>,
+    mut to_preview: Producer<[u8; 3]>,
+) -> Result<MidiInputConnection<()>> {
+    let (mut to_engine, mut from_input) = RingBuffer::new(INPUT_QUEUE_CAPACITY);
+    let (input, mut output) = open_ports(move |_timestamp, message, _| {
+        if let [status, data1, data2] = *message {
+            let event = MidiEvent {
+                data: [status, data1, data2],
+                delta_frames: 0,
+                live: true,
+                note_length: None,
+                note_offset: None,
+                detune: 0,
+                note_off_velocity: 0,
+            };
+            if to_engine.push((event, now())).is_err() {
+                eprintln!("dropped {:?}, the bend thread is behind", event.data);
+            }
+        }
+    })?;
+
+    let bend_state = state.clone();
+    let mut engine = Engine::new(state);
+    thread::spawn(move || loop {
+        while let Ok((event, time)) = from_input.pop() {
+            engine.receive_midi(&bend_state, event, time);
+        }
+        for event in engine.bend(&bend_state, now()) {
+            if let Err(e) = output.send(&event.data) {
+                eprintln!("failed to send {:?}: {e}", event.data);
+            }
+            // without an audio output nobody drains the queue, once full the preview misses events
+            let _ = to_preview.push(event.data);
+        }
+        thread::sleep(BEND_INTERVAL);
+    });
+    Ok(input)
+}
+
+// the preview synth on the default output device, the stream stops when dropped
+fn open_audio(state: &Arc<EditorState>, from_bender: Consumer<[u8; 3]>) -> Result<Stream> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| anyhow!("no audio output device"))?;
+    let supported = device.default_output_config()?;
+    let config = supported.config();
+    let state = state.clone();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, state, from_bender)?,
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, state, from_bender)?,
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, state, from_bender)?,
+    };
+    stream.play()?;
+    Ok(stream)
+}
+
+// renders the mono preview synth into every channel of the device's interleaved buffer
+fn build_stream<T: Sample>(
+    device: &Device,
+    config: &StreamConfig,
+    state: Arc<EditorState>,
+    mut from_bender: Consumer<[u8; 3]>,
+) -> Result<Stream> {
+    let channels = config.channels as usize;
+    let mut synth = PreviewSynth::new();
+    synth.sample_rate = config.sample_rate.0 as f32;
+    let mut scratch = Vec::with_capacity(RENDER_CAPACITY);
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &OutputCallbackInfo| {
+            state.update_preview_synth(&mut synth);
+            while let Ok(event) = from_bender.pop() {
+                synth.handle_event(event);
+            }
+            synth.render_chunks(&mut scratch, data.len() / channels, |offset, chunk| {
+                let frames = data[offset * channels..].chunks_mut(channels);
+                for (frame, sample) in frames.zip(chunk) {
+                    frame.fill(T::from(sample));
+                }
+            });
+        },
+        |e| eprintln!("audio output failed: {e}"),
+    )?;
+    Ok(stream)
+}
+
+// "ui render ..." writes a wav file instead of opening the editor
+fn run_render(args: &[String]) -> Result<()> {
+    let options = render::RenderOptions::from_args(args)?;
+    let samples = render::render(&options)?;
+    render::write_wav(&options.output, options.sample_rate, &samples)?;
+    println!("rendered {}", options.output.display());
+    Ok(())
+}
+
+// "ui lv2-bundle DIR [LIBRARY]" copies the built library into an LV2 bundle with its ttl
+fn run_lv2_bundle(args: &[String]) -> Result<()> {
+    let (bundle, library) = match args {
+        [bundle] => (bundle.as_str(), "target/release/libgliss.so"),
+        [bundle, library] => (bundle.as_str(), library.as_str()),
+        _ => return Err(anyhow!("usage: ui lv2-bundle DIR [LIBRARY]")),
+    };
+    lv2::write_bundle(Path::new(bundle), Path::new(library))?;
+    println!("wrote {bundle}");
+    Ok(())
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let subcommand = match args.first().map(String::as_str) {
+        Some("render") => Some(run_render(&args[1..])),
+        Some("lv2-bundle") => Some(run_lv2_bundle(&args[1..])),
+        _ => None,
+    };
+    if let Some(result) = subcommand {
+        if let Err(e) = result {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let state = Arc::new(EditorState::new());
+    state.set_parameters_to_default();
 
-    // TODO is it possible to capture midi via midir and use Gliss::capture_chords lookalike to update EditorState?
-    // move capture_chords to EditorState?
-    //
-    // first: test in daw
+    let (to_preview, from_bender) = RingBuffer::new(PREVIEW_QUEUE_CAPACITY);
+    let _audio_output = match open_audio(&state, from_bender) {
+        Ok(stream) => Some(stream),
+        Err(e) => {
+            eprintln!("running without audio: {e}");
+            None
+        }
+    };
+    let _midi_input = match connect_midi(&state, to_preview) {
+        Ok(midi_input) => Some(midi_input),
+        Err(e) => {
+            eprintln!("running without midi: {e}");
+            None
+        }
+    };
 
     let _window_handle = EguiWindow::open_blocking(
         ui::settings(),
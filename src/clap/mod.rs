@@ -0,0 +1,924 @@
+// the same engine and params as the VST2 plugin, exported from the same library as a CLAP plugin
+pub mod ffi;
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use vst::event::MidiEvent;
+
+use crate::engine::Engine;
+use crate::state::{EditorState, GlissParam, GLISS_PARAMETERS};
+use crate::synth::{PreviewSynth, RENDER_CAPACITY};
+
+use ffi::*;
+
+const PLUGIN_ID: &[u8] = b"com.joshuapostel.deepgliss\0";
+
+struct Features([*const c_char; 3]);
+
+// only ever points at static strings
+unsafe impl Sync for Features {}
+
+static FEATURES: Features = Features([
+    b"note-effect\0".as_ptr() as *const c_char,
+    b"utility\0".as_ptr() as *const c_char,
+    ptr::null(),
+]);
+
+static DESCRIPTOR: clap_plugin_descriptor = clap_plugin_descriptor {
+    clap_version: CLAP_VERSION,
+    id: PLUGIN_ID.as_ptr() as *const c_char,
+    name: b"DeepGliss\0".as_ptr() as *const c_char,
+    vendor: b"JoshuaPostel\0".as_ptr() as *const c_char,
+    url: b"https://joshuapostel.github.io/deep-gliss\0".as_ptr() as *const c_char,
+    manual_url: b"\0".as_ptr() as *const c_char,
+    support_url: b"\0".as_ptr() as *const c_char,
+    version: b"0.1.0\0".as_ptr() as *const c_char,
+    description: b"Continuous pitch bend between chords\0".as_ptr() as *const c_char,
+    features: FEATURES.0.as_ptr(),
+};
+
+#[allow(non_upper_case_globals)]
+#[no_mangle]
+pub static clap_entry: clap_plugin_entry = clap_plugin_entry {
+    clap_version: CLAP_VERSION,
+    init: entry_init,
+    deinit: entry_deinit,
+    get_factory: entry_get_factory,
+};
+
+static FACTORY: clap_plugin_factory = clap_plugin_factory {
+    get_plugin_count: factory_get_plugin_count,
+    get_plugin_descriptor: factory_get_plugin_descriptor,
+    create_plugin: factory_create_plugin,
+};
+
+static PARAMS: clap_plugin_params = clap_plugin_params {
+    count: params_count,
+    get_info: params_get_info,
+    get_value: params_get_value,
+    value_to_text: params_value_to_text,
+    text_to_value: params_text_to_value,
+    flush: params_flush,
+};
+
+static NOTE_PORTS: clap_plugin_note_ports = clap_plugin_note_ports {
+    count: note_ports_count,
+    get: note_ports_get,
+};
+
+static AUDIO_PORTS: clap_plugin_audio_ports = clap_plugin_audio_ports {
+    count: audio_ports_count,
+    get: audio_ports_get,
+};
+
+static STATE: clap_plugin_state = clap_plugin_state {
+    save: state_save,
+    load: state_load,
+};
+
+// audio thread only, or the main thread while not processing, so never contended
+struct AudioThread {
+    engine: Engine,
+    synth: PreviewSynth,
+    buffer: Vec<f32>,
+}
+
+struct ClapGliss {
+    plugin: clap_plugin,
+    state: Arc<EditorState>,
+    audio: Mutex<AudioThread>,
+}
+
+unsafe fn gliss<'a>(plugin: *const clap_plugin) -> &'a ClapGliss {
+    &*((*plugin).plugin_data as *const ClapGliss)
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as f64
+}
+
+fn param_by_id(param_id: clap_id) -> Option<GlissParam> {
+    GLISS_PARAMETERS.get(param_id as usize).copied()
+}
+
+unsafe fn push_event<T>(out: *const clap_output_events, event: &T) {
+    ((*out).try_push)(out, event as *const T as *const clap_event_header);
+}
+
+fn midi_event(data: [u8; 3]) -> MidiEvent {
+    MidiEvent {
+        data,
+        delta_frames: 0,
+        live: true,
+        note_length: None,
+        note_offset: None,
+        detune: 0,
+        note_off_velocity: 0,
+    }
+}
+
+// notes and midi go to the engine, param values from the host are not echoed back as automation
+unsafe fn receive_events(
+    state: &EditorState,
+    engine: &mut Engine,
+    in_events: *const clap_input_events,
+    now: f64,
+) {
+    if in_events.is_null() {
+        return;
+    }
+    let mut received_params = false;
+    for index in 0..((*in_events).size)(in_events) {
+        let header = ((*in_events).get)(in_events, index);
+        if header.is_null() || (*header).space_id != CLAP_CORE_EVENT_SPACE_ID {
+            continue;
+        }
+        match (*header).type_ {
+            CLAP_EVENT_NOTE_ON | CLAP_EVENT_NOTE_OFF => {
+                let note = &*(header as *const clap_event_note);
+                let status = if (*header).type_ == CLAP_EVENT_NOTE_ON {
+                    144
+                } else {
+                    128
+                };
+                let channel = note.channel.max(0) as u8 & 0x0F;
+                let velocity = (note.velocity * 127.0).round() as u8;
+                let event = midi_event([status + channel, note.key.max(0) as u8, velocity]);
+                engine.receive_midi(state, event, now);
+            }
+            CLAP_EVENT_MIDI => {
+                let midi = &*(header as *const clap_event_midi);
+                engine.receive_midi(state, midi_event(midi.data), now);
+            }
+            CLAP_EVENT_PARAM_VALUE => {
+                let value = &*(header as *const clap_event_param_value);
+                if let Some(param) = param_by_id(value.param_id) {
+                    let index = value.param_id as usize;
+                    state
+                        .params
+                        .set_parameter(index, param.plain_to_daw(value.value));
+                    received_params = true;
+                }
+            }
+            _ => (),
+        }
+    }
+    if received_params {
+        state.params.iterate(true).for_each(drop);
+    }
+}
+
+// ui edits become gestures and param values for the host, like Gliss::process does for VST2
+unsafe fn send_automation(state: &EditorState, out_events: *const clap_output_events) {
+    if out_events.is_null() {
+        return;
+    }
+    let host_edits = state.take_host_edits();
+    for edit in host_edits.begun() {
+        let event = clap_event_param_gesture {
+            header: clap_event_header::new::<clap_event_param_gesture>(
+                CLAP_EVENT_PARAM_GESTURE_BEGIN,
+            ),
+            param_id: edit.index() as clap_id,
+        };
+        push_event(out_events, &event);
+    }
+    for (index, value) in state.params.iterate(true) {
+        let event = clap_event_param_value {
+            header: clap_event_header::new::<clap_event_param_value>(CLAP_EVENT_PARAM_VALUE),
+            param_id: index as clap_id,
+            cookie: ptr::null_mut(),
+            note_id: -1,
+            port_index: -1,
+            channel: -1,
+            key: -1,
+            value: GLISS_PARAMETERS[index].daw_to_plain(value),
+        };
+        push_event(out_events, &event);
+    }
+    for edit in host_edits.ended() {
+        let event = clap_event_param_gesture {
+            header: clap_event_header::new::<clap_event_param_gesture>(
+                CLAP_EVENT_PARAM_GESTURE_END,
+            ),
+            param_id: edit.index() as clap_id,
+        };
+        push_event(out_events, &event);
+    }
+}
+
+unsafe extern "C" fn entry_init(_plugin_path: *const c_char) -> bool {
+    true
+}
+
+unsafe extern "C" fn entry_deinit() {}
+
+unsafe extern "C" fn entry_get_factory(factory_id: *const c_char) -> *const c_void {
+    if CStr::from_ptr(factory_id).to_bytes_with_nul() == CLAP_PLUGIN_FACTORY_ID {
+        &FACTORY as *const clap_plugin_factory as *const c_void
+    } else {
+        ptr::null()
+    }
+}
+
+unsafe extern "C" fn factory_get_plugin_count(_factory: *const clap_plugin_factory) -> u32 {
+    1
+}
+
+unsafe extern "C" fn factory_get_plugin_descriptor(
+    _factory: *const clap_plugin_factory,
+    index: u32,
+) -> *const clap_plugin_descriptor {
+    if index == 0 {
+        &DESCRIPTOR
+    } else {
+        ptr::null()
+    }
+}
+
+unsafe extern "C" fn factory_create_plugin(
+    _factory: *const clap_plugin_factory,
+    _host: *const clap_host,
+    plugin_id: *const c_char,
+) -> *const clap_plugin {
+    if CStr::from_ptr(plugin_id).to_bytes_with_nul() != PLUGIN_ID {
+        return ptr::null();
+    }
+    let state = Arc::new(EditorState::new());
+    let gliss = Box::into_raw(Box::new(ClapGliss {
+        plugin: clap_plugin {
+            desc: &DESCRIPTOR,
+            plugin_data: ptr::null_mut(),
+            init: plugin_init,
+            destroy: plugin_destroy,
+            activate: plugin_activate,
+            deactivate: plugin_deactivate,
+            start_processing: plugin_start_processing,
+            stop_processing: plugin_stop_processing,
+            reset: plugin_reset,
+            process: plugin_process,
+            get_extension: plugin_get_extension,
+            on_main_thread: plugin_on_main_thread,
+        },
+        audio: Mutex::new(AudioThread {
+            engine: Engine::new(&state),
+            synth: PreviewSynth::new(),
+            buffer: Vec::with_capacity(RENDER_CAPACITY),
+        }),
+        state,
+    }));
+    (*gliss).plugin.plugin_data = gliss as *mut c_void;
+    &(*gliss).plugin
+}
+
+unsafe extern "C" fn plugin_init(plugin: *const clap_plugin) -> bool {
+    gliss(plugin).state.set_parameters_to_default();
+    true
+}
+
+unsafe extern "C" fn plugin_destroy(plugin: *const clap_plugin) {
+    drop(Box::from_raw((*plugin).plugin_data as *mut ClapGliss));
+}
+
+unsafe extern "C" fn plugin_activate(
+    plugin: *const clap_plugin,
+    sample_rate: f64,
+    _min_frames_count: u32,
+    max_frames_count: u32,
+) -> bool {
+    let mut audio = gliss(plugin).audio.lock().unwrap();
+    audio.synth.sample_rate = sample_rate as f32;
+    audio.buffer.reserve(max_frames_count as usize);
+    true
+}
+
+unsafe extern "C" fn plugin_deactivate(_plugin: *const clap_plugin) {}
+
+unsafe extern "C" fn plugin_start_processing(_plugin: *const clap_plugin) -> bool {
+    true
+}
+
+unsafe extern "C" fn plugin_stop_processing(_plugin: *const clap_plugin) {}
+
+unsafe extern "C" fn plugin_reset(_plugin: *const clap_plugin) {}
+
+unsafe extern "C" fn plugin_process(
+    plugin: *const clap_plugin,
+    process: *const clap_process,
+) -> i32 {
+    process_at(gliss(plugin), &*process, now())
+}
+
+// one block at the given time, tests pass a simulated clock
+unsafe fn process_at(gliss: &ClapGliss, process: &clap_process, now: f64) -> i32 {
+    let mut audio = gliss.audio.lock().unwrap();
+    let AudioThread {
+        engine,
+        synth,
+        buffer,
+    } = &mut *audio;
+    send_automation(&gliss.state, process.out_events);
+    receive_events(&gliss.state, engine, process.in_events, now);
+
+    let events = engine.bend(&gliss.state, now);
+    if !process.out_events.is_null() {
+        for event in events.iter() {
+            let midi = clap_event_midi {
+                header: clap_event_header::new::<clap_event_midi>(CLAP_EVENT_MIDI),
+                port_index: 0,
+                data: event.data,
+            };
+            push_event(process.out_events, &midi);
+        }
+    }
+
+    gliss.state.update_preview_synth(synth);
+    for event in events.iter() {
+        synth.handle_event(event.data);
+    }
+    let has_output = process.audio_outputs_count > 0 && !process.audio_outputs.is_null();
+    synth.render_chunks(buffer, process.frames_count as usize, |offset, chunk| {
+        if !has_output {
+            return;
+        }
+        let output = &*process.audio_outputs;
+        for channel in 0..output.channel_count as usize {
+            let data = *output.data32.add(channel);
+            if !data.is_null() {
+                std::slice::from_raw_parts_mut(data.add(offset), chunk.len())
+                    .copy_from_slice(chunk);
+            }
+        }
+    });
+    CLAP_PROCESS_CONTINUE
+}
+
+unsafe extern "C" fn plugin_get_extension(
+    _plugin: *const clap_plugin,
+    id: *const c_char,
+) -> *const c_void {
+    match CStr::from_ptr(id).to_bytes_with_nul() {
+        id if id == CLAP_EXT_PARAMS => &PARAMS as *const clap_plugin_params as *const c_void,
+        id if id == CLAP_EXT_NOTE_PORTS => {
+            &NOTE_PORTS as *const clap_plugin_note_ports as *const c_void
+        }
+        id if id == CLAP_EXT_AUDIO_PORTS => {
+            &AUDIO_PORTS as *const clap_plugin_audio_ports as *const c_void
+        }
+        id if id == CLAP_EXT_STATE => &STATE as *const clap_plugin_state as *const c_void,
+        _ => ptr::null(),
+    }
+}
+
+unsafe extern "C" fn plugin_on_main_thread(_plugin: *const clap_plugin) {}
+
+unsafe extern "C" fn params_count(_plugin: *const clap_plugin) -> u32 {
+    GLISS_PARAMETERS.len() as u32
+}
+
+unsafe extern "C" fn params_get_info(
+    _plugin: *const clap_plugin,
+    param_index: u32,
+    param_info: *mut clap_param_info,
+) -> bool {
+    let param = match param_by_id(param_index) {
+        Some(param) => param,
+        None => return false,
+    };
+    let config = param.get_config();
+    let info = &mut *param_info;
+    info.id = param_index;
+    info.flags = CLAP_PARAM_IS_AUTOMATABLE;
+    if config.is_integer || param.is_categorical() {
+        info.flags |= CLAP_PARAM_IS_STEPPED;
+    }
+    info.cookie = ptr::null_mut();
+    write_c_str(&mut info.name, config.daw_name);
+    write_c_str(&mut info.module, "");
+    info.min_value = param.daw_to_plain(0.0);
+    info.max_value = param.daw_to_plain(1.0);
+    info.default_value = param.daw_to_plain(config.map_to_daw(config.default));
+    true
+}
+
+unsafe extern "C" fn params_get_value(
+    plugin: *const clap_plugin,
+    param_id: clap_id,
+    out_value: *mut f64,
+) -> bool {
+    match param_by_id(param_id) {
+        Some(param) => {
+            *out_value = param.daw_to_plain(gliss(plugin).state.get_parameter(param));
+            true
+        }
+        None => false,
+    }
+}
+
+unsafe extern "C" fn params_value_to_text(
+    _plugin: *const clap_plugin,
+    param_id: clap_id,
+    value: f64,
+    out_buffer: *mut c_char,
+    out_buffer_capacity: u32,
+) -> bool {
+    match param_by_id(param_id) {
+        Some(param) if !out_buffer.is_null() => {
+            let buffer = std::slice::from_raw_parts_mut(out_buffer, out_buffer_capacity as usize);
+            write_c_str(buffer, &param.display_text(param.plain_to_daw(value)));
+            true
+        }
+        _ => false,
+    }
+}
+
+unsafe extern "C" fn params_text_to_value(
+    _plugin: *const clap_plugin,
+    param_id: clap_id,
+    param_value_text: *const c_char,
+    out_value: *mut f64,
+) -> bool {
+    let param = match param_by_id(param_id) {
+        Some(param) => param,
+        None => return false,
+    };
+    let text = CStr::from_ptr(param_value_text).to_string_lossy();
+    match param.parse_text(&text) {
+        Some(daw_value) => {
+            *out_value = param.daw_to_plain(daw_value);
+            true
+        }
+        None => false,
+    }
+}
+
+unsafe extern "C" fn params_flush(
+    plugin: *const clap_plugin,
+    in_: *const clap_input_events,
+    out: *const clap_output_events,
+) {
+    let gliss = gliss(plugin);
+    send_automation(&gliss.state, out);
+    let mut audio = gliss.audio.lock().unwrap();
+    receive_events(&gliss.state, &mut audio.engine, in_, now());
+}
+
+unsafe extern "C" fn note_ports_count(_plugin: *const clap_plugin, _is_input: bool) -> u32 {
+    1
+}
+
+unsafe extern "C" fn note_ports_get(
+    _plugin: *const clap_plugin,
+    index: u32,
+    is_input: bool,
+    info: *mut clap_note_port_info,
+) -> bool {
+    if index != 0 {
+        return false;
+    }
+    let info = &mut *info;
+    info.id = 0;
+    // pitch bends need midi out
+    if is_input {
+        info.supported_dialects = CLAP_NOTE_DIALECT_CLAP | CLAP_NOTE_DIALECT_MIDI;
+        write_c_str(&mut info.name, "Chords In");
+    } else {
+        info.supported_dialects = CLAP_NOTE_DIALECT_MIDI;
+        write_c_str(&mut info.name, "Glissandi Out");
+    }
+    info.preferred_dialect = CLAP_NOTE_DIALECT_MIDI;
+    true
+}
+
+// the preview synth
+unsafe extern "C" fn audio_ports_count(_plugin: *const clap_plugin, is_input: bool) -> u32 {
+    if is_input {
+        0
+    } else {
+        1
+    }
+}
+
+unsafe extern "C" fn audio_ports_get(
+    _plugin: *const clap_plugin,
+    index: u32,
+    is_input: bool,
+    info: *mut clap_audio_port_info,
+) -> bool {
+    if is_input || index != 0 {
+        return false;
+    }
+    let info = &mut *info;
+    info.id = 0;
+    write_c_str(&mut info.name, "Preview");
+    info.flags = CLAP_AUDIO_PORT_IS_MAIN;
+    info.channel_count = 2;
+    info.port_type = CLAP_PORT_STEREO.as_ptr() as *const c_char;
+    info.in_place_pair = CLAP_INVALID_ID;
+    true
+}
+
+// the same text as the VST2 chunks
+unsafe extern "C" fn state_save(plugin: *const clap_plugin, stream: *const clap_ostream) -> bool {
+    let mut data = vec![];
+    if let Err(e) = gliss(plugin).state.write_state(&mut data) {
+        log::error!("failed to save plugin state: {e}");
+        return false;
+    }
+    let mut written = 0;
+    while written < data.len() {
+        let remaining = &data[written..];
+        let n = ((*stream).write)(
+            stream,
+            remaining.as_ptr() as *const c_void,
+            remaining.len() as u64,
+        );
+        if n <= 0 {
+            return false;
+        }
+        written += n as usize;
+    }
+    true
+}
+
+unsafe extern "C" fn state_load(plugin: *const clap_plugin, stream: *const clap_istream) -> bool {
+    let mut data = vec![];
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = ((*stream).read)(
+            stream,
+            chunk.as_mut_ptr() as *mut c_void,
+            chunk.len() as u64,
+        );
+        match n {
+            0 => break,
+            n if n < 0 => return false,
+            n => data.extend_from_slice(&chunk[..n as usize]),
+        }
+    }
+    match gliss(plugin)
+        .state
+        .load_state(&String::from_utf8_lossy(&data))
+    {
+        Ok(report) => {
+            if !report.is_complete() {
+                log::warn!("loaded plugin state: {report}");
+            }
+            true
+        }
+        Err(e) => {
+            log::error!("failed to load plugin state: {e}");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod clap_entry_points {
+    use super::clap_entry;
+    use super::ffi::*;
+    use crate::audio_scenario::{bends_without_allocating, PluginFormat, BLOCK_SIZE, SAMPLE_RATE};
+    use crate::state::{EditorState, GlissParam};
+    use crate::synth::Waveform;
+
+    use std::ffi::CStr;
+    use std::os::raw::{c_char, c_void};
+    use std::ptr;
+
+    unsafe extern "C" fn host_get_extension(
+        _host: *const clap_host,
+        _extension_id: *const c_char,
+    ) -> *const c_void {
+        ptr::null()
+    }
+
+    unsafe extern "C" fn host_request(_host: *const clap_host) {}
+
+    fn host() -> clap_host {
+        clap_host {
+            clap_version: CLAP_VERSION,
+            host_data: ptr::null_mut(),
+            name: b"test\0".as_ptr() as *const c_char,
+            vendor: b"\0".as_ptr() as *const c_char,
+            url: b"\0".as_ptr() as *const c_char,
+            version: b"\0".as_ptr() as *const c_char,
+            get_extension: host_get_extension,
+            request_restart: host_request,
+            request_process: host_request,
+            request_callback: host_request,
+        }
+    }
+
+    unsafe fn create_plugin(host: &clap_host) -> *const clap_plugin {
+        let factory = (clap_entry.get_factory)(CLAP_PLUGIN_FACTORY_ID.as_ptr() as *const c_char)
+            as *const clap_plugin_factory;
+        assert!(!factory.is_null());
+        assert_eq!(((*factory).get_plugin_count)(factory), 1);
+        let descriptor = ((*factory).get_plugin_descriptor)(factory, 0);
+        let plugin = ((*factory).create_plugin)(factory, host, (*descriptor).id);
+        assert!(((*plugin).init)(plugin));
+        plugin
+    }
+
+    unsafe fn extension<T>(plugin: *const clap_plugin, id: &[u8]) -> &'static T {
+        let extension = ((*plugin).get_extension)(plugin, id.as_ptr() as *const c_char);
+        assert!(!extension.is_null());
+        &*(extension as *const T)
+    }
+
+    #[test]
+    fn params_use_param_config_ranges() {
+        let host = host();
+        unsafe {
+            let plugin = create_plugin(&host);
+            let params: &clap_plugin_params = extension(plugin, CLAP_EXT_PARAMS);
+            assert_eq!((params.count)(plugin), 49);
+
+            let mut info: clap_param_info = std::mem::zeroed();
+            assert!((params.get_info)(plugin, 2, &mut info));
+            let name = CStr::from_ptr(info.name.as_ptr()).to_str().unwrap();
+            let config = GlissParam::HoldDuration.get_config();
+            assert_eq!(name, config.daw_name);
+            assert_eq!(info.min_value, config.min);
+            assert_eq!(info.max_value, config.max);
+
+            let mut value = 0.0;
+            let text = b"3.5 secs\0";
+            assert!((params.text_to_value)(
+                plugin,
+                2,
+                text.as_ptr() as *const c_char,
+                &mut value
+            ));
+            assert!((value - 3.5).abs() < 0.0001);
+            let mut buffer = [0 as c_char; 64];
+            assert!((params.value_to_text)(
+                plugin,
+                2,
+                value,
+                buffer.as_mut_ptr(),
+                64
+            ));
+            let text = CStr::from_ptr(buffer.as_ptr()).to_str().unwrap();
+            assert_eq!(text, "3.50 secs");
+
+            let mut info: clap_param_info = std::mem::zeroed();
+            let index = super::GLISS_PARAMETERS
+                .iter()
+                .position(|param| *param == GlissParam::PreviewWaveform)
+                .unwrap();
+            assert!((params.get_info)(plugin, index as u32, &mut info));
+            assert_eq!(info.max_value, 2.0);
+            assert!(info.flags & CLAP_PARAM_IS_STEPPED != 0);
+            let mut buffer = [0 as c_char; 64];
+            (params.value_to_text)(plugin, index as u32, 2.0, buffer.as_mut_ptr(), 64);
+            let text = CStr::from_ptr(buffer.as_ptr()).to_str().unwrap();
+            assert_eq!(text, format!("{:?}", Waveform::Saw));
+
+            // enum-backed params step through their options by name
+            let index = super::GLISS_PARAMETERS
+                .iter()
+                .position(|param| *param == GlissParam::BendMapping)
+                .unwrap();
+            let mut info: clap_param_info = std::mem::zeroed();
+            assert!((params.get_info)(plugin, index as u32, &mut info));
+            assert!(info.flags & CLAP_PARAM_IS_STEPPED != 0);
+            assert_eq!(info.max_value, 4.0);
+            (params.value_to_text)(plugin, index as u32, 2.0, buffer.as_mut_ptr(), 64);
+            let text = CStr::from_ptr(buffer.as_ptr()).to_str().unwrap();
+            assert_eq!(text, "Random");
+
+            ((*plugin).destroy)(plugin);
+        }
+    }
+
+    #[test]
+    fn midi_note_ports_in_and_out() {
+        let host = host();
+        unsafe {
+            let plugin = create_plugin(&host);
+            let note_ports: &clap_plugin_note_ports = extension(plugin, CLAP_EXT_NOTE_PORTS);
+            for is_input in [true, false] {
+                assert_eq!((note_ports.count)(plugin, is_input), 1);
+                let mut info: clap_note_port_info = std::mem::zeroed();
+                assert!((note_ports.get)(plugin, 0, is_input, &mut info));
+                assert!(info.supported_dialects & CLAP_NOTE_DIALECT_MIDI != 0);
+            }
+            ((*plugin).destroy)(plugin);
+        }
+    }
+
+    unsafe extern "C" fn write_to_vec(
+        stream: *const clap_ostream,
+        buffer: *const c_void,
+        size: u64,
+    ) -> i64 {
+        let data = &mut *((*stream).ctx as *mut Vec<u8>);
+        data.extend_from_slice(std::slice::from_raw_parts(
+            buffer as *const u8,
+            size as usize,
+        ));
+        size as i64
+    }
+
+    unsafe extern "C" fn read_from_slice(
+        stream: *const clap_istream,
+        buffer: *mut c_void,
+        size: u64,
+    ) -> i64 {
+        let data = &mut *((*stream).ctx as *mut &[u8]);
+        let n = data.len().min(size as usize);
+        ptr::copy_nonoverlapping(data.as_ptr(), buffer as *mut u8, n);
+        *data = &data[n..];
+        n as i64
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let host = host();
+        unsafe {
+            let plugin = create_plugin(&host);
+            let params: &clap_plugin_params = extension(plugin, CLAP_EXT_PARAMS);
+            let state: &clap_plugin_state = extension(plugin, CLAP_EXT_STATE);
+            super::gliss(plugin)
+                .state
+                .set_parameter(GlissParam::HoldDuration, 6.0);
+
+            let mut data: Vec<u8> = vec![];
+            let ostream = clap_ostream {
+                ctx: &mut data as *mut Vec<u8> as *mut c_void,
+                write: write_to_vec,
+            };
+            assert!((state.save)(plugin, &ostream));
+
+            let restored = create_plugin(&host);
+            let mut remaining: &[u8] = &data;
+            let istream = clap_istream {
+                ctx: &mut remaining as *mut &[u8] as *mut c_void,
+                read: read_from_slice,
+            };
+            assert!((state.load)(restored, &istream));
+            let mut value = 0.0;
+            assert!((params.get_value)(restored, 2, &mut value));
+            assert!((value - 6.0).abs() < 0.0001);
+
+            ((*plugin).destroy)(plugin);
+            ((*restored).destroy)(restored);
+        }
+    }
+
+    unsafe extern "C" fn notes_size(list: *const clap_input_events) -> u32 {
+        (*((*list).ctx as *const Vec<clap_event_note>)).len() as u32
+    }
+
+    unsafe extern "C" fn notes_get(
+        list: *const clap_input_events,
+        index: u32,
+    ) -> *const clap_event_header {
+        let notes = &*((*list).ctx as *const Vec<clap_event_note>);
+        &notes[index as usize].header
+    }
+
+    // what process pushed to the host
+    #[derive(Default)]
+    struct Pushed {
+        midi: usize,
+        gestures_begun: usize,
+        gestures_ended: usize,
+    }
+
+    unsafe extern "C" fn count_pushed(
+        list: *const clap_output_events,
+        event: *const clap_event_header,
+    ) -> bool {
+        let pushed = &mut *((*list).ctx as *mut Pushed);
+        match (*event).type_ {
+            CLAP_EVENT_MIDI => pushed.midi += 1,
+            CLAP_EVENT_PARAM_GESTURE_BEGIN => pushed.gestures_begun += 1,
+            CLAP_EVENT_PARAM_GESTURE_END => pushed.gestures_ended += 1,
+            _ => (),
+        }
+        true
+    }
+
+    // an activated plugin driven through process_at, notes arrive as CLAP note events
+    struct Clap {
+        plugin: *const clap_plugin,
+        notes: Vec<clap_event_note>,
+        pushed: Pushed,
+        left: Vec<f32>,
+        right: Vec<f32>,
+    }
+
+    impl Clap {
+        unsafe fn new() -> Self {
+            let plugin = create_plugin(&host());
+            assert!(((*plugin).activate)(
+                plugin,
+                SAMPLE_RATE as f64,
+                1,
+                BLOCK_SIZE as u32
+            ));
+            Clap {
+                plugin,
+                notes: Vec::with_capacity(16),
+                pushed: Pushed::default(),
+                left: vec![0.0; BLOCK_SIZE],
+                right: vec![0.0; BLOCK_SIZE],
+            }
+        }
+    }
+
+    impl Drop for Clap {
+        fn drop(&mut self) {
+            unsafe { ((*self.plugin).destroy)(self.plugin) }
+        }
+    }
+
+    impl PluginFormat for Clap {
+        fn state(&self) -> &EditorState {
+            unsafe { &super::gliss(self.plugin).state }
+        }
+
+        fn set_param(&mut self, param: GlissParam, plain: f64) {
+            let index = super::GLISS_PARAMETERS
+                .iter()
+                .position(|p| *p == param)
+                .unwrap();
+            self.state()
+                .params
+                .set_parameter(index, param.plain_to_daw(plain));
+        }
+
+        fn send_notes(&mut self, midi_numbers: &[u8]) {
+            for midi_number in midi_numbers {
+                self.notes.push(clap_event_note {
+                    header: clap_event_header::new::<clap_event_note>(CLAP_EVENT_NOTE_ON),
+                    note_id: -1,
+                    port_index: 0,
+                    channel: 0,
+                    key: *midi_number as i16,
+                    velocity: 0.8,
+                });
+            }
+        }
+
+        fn process(&mut self, now: f64) -> usize {
+            let in_events = clap_input_events {
+                ctx: &mut self.notes as *mut Vec<clap_event_note> as *mut c_void,
+                size: notes_size,
+                get: notes_get,
+            };
+            let out_events = clap_output_events {
+                ctx: &mut self.pushed as *mut Pushed as *mut c_void,
+                try_push: count_pushed,
+            };
+            let mut channels = [self.left.as_mut_ptr(), self.right.as_mut_ptr()];
+            let mut output = clap_audio_buffer {
+                data32: channels.as_mut_ptr(),
+                data64: ptr::null_mut(),
+                channel_count: 2,
+                latency: 0,
+                constant_mask: 0,
+            };
+            let process = clap_process {
+                steady_time: -1,
+                frames_count: BLOCK_SIZE as u32,
+                transport: ptr::null(),
+                audio_inputs: ptr::null(),
+                audio_outputs: &mut output,
+                audio_inputs_count: 0,
+                audio_outputs_count: 1,
+                in_events: &in_events,
+                out_events: &out_events,
+            };
+            let before = self.pushed.midi;
+            unsafe { super::process_at(super::gliss(self.plugin), &process, now) };
+            self.notes.clear();
+            self.pushed.midi - before
+        }
+
+        fn output(&self) -> &[f32] {
+            &self.left
+        }
+    }
+
+    #[test]
+    fn bending_runs_without_allocating() {
+        let mut clap = unsafe { Clap::new() };
+        bends_without_allocating(&mut clap);
+        // the ui gesture reaches the host
+        assert_eq!(
+            (clap.pushed.gestures_begun, clap.pushed.gestures_ended),
+            (1, 1)
+        );
+        assert_eq!(clap.left, clap.right);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::draw::theme::GLISS_THEME;
-use crate::midi::mapper::ChordMap;
+use crate::midi::mapper::{ChordMap, MappingPreference};
 use crate::midi::paths::Path;
 use crate::state::GlissParam::*;
 use crate::state::{EditorState, GlissParam, ParamConfig};
@@ -92,10 +92,43 @@ pub fn draw_map_button(
     state: &Arc<EditorState>,
     map_variant: ChordMap,
     selected: bool,
+    params: Vec<GlissParam>,
 ) {
     let label = SelectableLabel::new(false, text);
-    if ui.put(to_rect, label).clicked() {
+    let response = ui.put(to_rect, label);
+    if response.clicked() {
         state.set_parameter(BendMapping, map_variant.as_f64());
+        if !params.is_empty() {
+            let mut editor_params = state.editor_params.lock().unwrap();
+            *editor_params = params;
+        }
+    }
+    if response.secondary_clicked() {
+        state.learn_midi(BendMapping);
+    }
+    draw_linesegments(ui, lines, from_rect, to_rect, selected);
+    draw_notes(ui, notes, from_rect, to_rect, selected);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn draw_preference_button(
+    ui: &mut Ui,
+    text: &str,
+    lines: Vec<[Pos2; 2]>,
+    notes: Vec<&Pos2>,
+    from_rect: Rect,
+    to_rect: Rect,
+    state: &Arc<EditorState>,
+    preference_variant: MappingPreference,
+    selected: bool,
+) {
+    let label = SelectableLabel::new(false, text);
+    if ui
+        .put(to_rect, label)
+        .on_hover_text(format!("{preference_variant:?}"))
+        .clicked()
+    {
+        state.set_parameter(BendMappingPreference, preference_variant.as_f64());
     }
     draw_linesegments(ui, lines, from_rect, to_rect, selected);
     draw_notes(ui, notes, from_rect, to_rect, selected);
@@ -156,10 +189,7 @@ pub fn draw_path_button(
         }
     }
     if response.secondary_clicked() {
-        match *keyboard_focus {
-            Some(x) if x == path_variant => *keyboard_focus = None,
-            _ => *keyboard_focus = Some(path_variant),
-        }
+        state.learn_midi(BendPath);
     }
     //    if *keyboard_focus == Some(path_variant) {
     //        //let parameter_editor_rect = Rect::from_x_y_ranges(600.0..=900.0, 15.0..=150.0);
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use egui::Ui;
+
+use crate::EditorState;
+
+pub fn draw_history(ui: &mut Ui, state: &Arc<EditorState>) {
+    // a gesture ends once nothing is held down
+    if !ui.input().pointer.any_down() {
+        state.end_gesture();
+    }
+    let (can_undo, can_redo) = {
+        let history = state.history.lock().unwrap();
+        (history.can_undo(), history.can_redo())
+    };
+    if ui
+        .add_enabled(can_undo, egui::widgets::Button::new("Undo"))
+        .clicked()
+    {
+        state.undo();
+    }
+    if ui
+        .add_enabled(can_redo, egui::widgets::Button::new("Redo"))
+        .clicked()
+    {
+        state.redo();
+    }
+    let active = state.ab_slots.lock().unwrap().active;
+    for (slot, name) in ["A", "B"].iter().enumerate() {
+        if ui.selectable_label(active == slot, *name).clicked() {
+            state.select_ab_slot(slot);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use egui::Ui;
+
+use crate::state::KeyRange;
+use crate::EditorState;
+
+pub fn draw_key_range(ui: &mut Ui, state: &Arc<EditorState>) {
+    let mut key_range = state.key_range.lock().unwrap();
+    let mut lowest = *key_range.octaves.start();
+    let mut highest = *key_range.octaves.end();
+    ui.label("Keys");
+    // the lowest C and highest B shown
+    let low_response = ui.add_enabled(
+        !key_range.auto_fit,
+        egui::DragValue::new(&mut lowest)
+            .clamp_range(KeyRange::MIN_OCTAVE..=KeyRange::MAX_OCTAVE)
+            .speed(0.05)
+            .prefix("C"),
+    );
+    let high_response = ui.add_enabled(
+        !key_range.auto_fit,
+        egui::DragValue::new(&mut highest)
+            .clamp_range(KeyRange::MIN_OCTAVE..=KeyRange::MAX_OCTAVE)
+            .speed(0.05)
+            .prefix("B"),
+    );
+    if low_response.changed() {
+        key_range.set_octaves(lowest, highest.max(lowest));
+    } else if high_response.changed() {
+        key_range.set_octaves(lowest.min(highest), highest);
+    }
+    if ui
+        .selectable_label(key_range.auto_fit, "Fit")
+        .on_hover_text("follow the sounding voices")
+        .clicked()
+    {
+        key_range.auto_fit = !key_range.auto_fit;
+    }
+}
@@ -1,4 +1,6 @@
 pub mod button;
+pub mod history;
+pub mod key_range;
 pub mod parameter_editor;
 pub mod piano;
 pub mod preset;
@@ -14,19 +14,68 @@ pub fn draw_parameter_editor(
     let mut responses = vec![];
     let configs: Vec<ParamConfig> = params.iter().map(|param| param.get_config()).collect();
     let mut text_max_x_location: f32 = 0.0;
-    for (idx, config) in configs.iter().enumerate() {
+    for (idx, (config, param)) in configs.iter().zip(params.iter()).enumerate() {
         let i = idx as f32;
+        let midi_learn = &state.midi_learn;
+        let (name, color) = match midi_learn.controller_of(*param) {
+            _ if midi_learn.learning() == Some(*param) => {
+                (format!("{} [CC?]", config.ui_name), egui::Color32::WHITE)
+            }
+            Some(controller) => (
+                format!("{} [CC{}]", config.ui_name, controller),
+                egui::Color32::GRAY,
+            ),
+            None => (config.ui_name.to_string(), egui::Color32::GRAY),
+        };
         let location = ui.painter().text(
             Pos2::new(to_rect.min.x, to_rect.min.y + (i * 20.0) + 16.0),
             egui::Align2::LEFT_CENTER,
-            config.ui_name,
+            name,
             egui::TextStyle::Body,
-            egui::Color32::GRAY,
+            color,
         );
         text_max_x_location = text_max_x_location.max(location.max.x);
     }
     for (idx, (config, param)) in configs.iter().zip(params.into_iter()).enumerate() {
         let i = idx as f32;
+        let lock_rect = Rect::from_two_pos(
+            Pos2::new(to_rect.max.x - 20.0, to_rect.min.y + (i * 20.0) + 7.5),
+            Pos2::new(to_rect.max.x, to_rect.min.y + (i * 20.0) + 7.5),
+        );
+        let locked = state.locked_params.lock().unwrap().contains(&param);
+        let lock_response = ui
+            .put(lock_rect, egui::SelectableLabel::new(locked, "🔒"))
+            .on_hover_text("lock against the dice");
+        if lock_response.clicked() {
+            state.toggle_lock(param);
+        }
+        responses.push(lock_response);
+        if let Some(options) = param.get_options() {
+            let n_options = options.len();
+            let selected =
+                ((state.get_parameter(param) * n_options as f32) as usize).min(n_options - 1);
+            ui.horizontal(|ui| {
+                for (option_idx, option) in options.iter().enumerate() {
+                    let x = text_max_x_location + 5.0 + option_idx as f32 * 55.0;
+                    let option_rect = Rect::from_two_pos(
+                        Pos2::new(x, to_rect.min.y + (i * 20.0) + 7.5),
+                        Pos2::new(x + 50.0, to_rect.min.y + (i * 20.0) + 7.5),
+                    );
+                    let option_response = ui.put(
+                        option_rect,
+                        egui::SelectableLabel::new(option_idx == selected, *option),
+                    );
+                    if option_response.clicked() {
+                        state.set_parameter(param, option_idx as f64 / n_options as f64);
+                    }
+                    if option_response.secondary_clicked() {
+                        state.learn_midi(param);
+                    }
+                    responses.push(option_response);
+                }
+            });
+            continue;
+        }
         let mut val = state.get_ui_parameter(param);
         ui.horizontal(|ui| {
             let edit_rect = Rect::from_two_pos(
@@ -72,6 +121,9 @@ pub fn draw_parameter_editor(
                 if edit_randomness_response.changed() {
                     state.set_parameter(randomness_param, val);
                 };
+                if edit_randomness_response.secondary_clicked() {
+                    state.learn_midi(randomness_param);
+                }
                 responses.push(edit_randomness_response);
             }
             ui.label(config.unit);
@@ -79,6 +131,9 @@ pub fn draw_parameter_editor(
             if edit_response.changed() {
                 state.set_parameter(param, val);
             };
+            if edit_response.secondary_clicked() {
+                state.learn_midi(param);
+            }
             responses.push(edit_response);
         });
     }
@@ -8,14 +8,14 @@ use crate::draw::theme::GLISS_THEME;
 const WINDOW_WIDTH: usize = 1024;
 const WINDOW_HEIGHT: usize = 512;
 
-pub fn draw(rect: Rect, octive_range: RangeInclusive<u8>, active_notes: Vec<u8>) -> Vec<Shape> {
+pub fn draw(rect: Rect, octive_range: RangeInclusive<i8>, active_notes: Vec<u8>) -> Vec<Shape> {
     let mut shapes = vec![];
 
     let octave_height = (rect.max.y - rect.min.y) / octive_range.len() as f32;
     // flipped y because egui origin is top left
-    for (i, octave) in octive_range.rev().enumerate() {
+    for (i, octave) in octive_range.clone().rev().enumerate() {
         let mut active_octave_notes = vec![];
-        let octave_midi_notes = ((octave + 1) * 12)..((octave + 2) * 12);
+        let octave_midi_notes = ((octave + 1) as u8 * 12)..((octave + 2) as u8 * 12);
         for note in &active_notes {
             if octave_midi_notes.contains(note) {
                 active_octave_notes.push(note - octave_midi_notes.start);
@@ -28,9 +28,35 @@ pub fn draw(rect: Rect, octive_range: RangeInclusive<u8>, active_notes: Vec<u8>)
         let mut octave_shapes = draw_octave(octave_rect, active_octave_notes);
         shapes.append(&mut octave_shapes);
     }
+
+    // held keys outside the range point off the top or bottom of the piano
+    let lowest = (octive_range.start() + 1) as u16 * 12;
+    let highest = (octive_range.end() + 2) as u16 * 12;
+    let size = (rect.max.x - rect.min.x) / 4.0;
+    let color = GLISS_THEME.piano.black_active;
+    if active_notes.iter().any(|note| (*note as u16) < lowest) {
+        let tip = Pos2::new(rect.center().x, rect.max.y - 2.0);
+        shapes.push(draw_arrow(tip, false, size, color));
+    }
+    if active_notes.iter().any(|note| *note as u16 >= highest) {
+        let tip = Pos2::new(rect.center().x, rect.min.y + 2.0);
+        shapes.push(draw_arrow(tip, true, size, color));
+    }
     shapes
 }
 
+// a triangle pointing up or down at tip
+pub fn draw_arrow(tip: Pos2, up: bool, size: f32, color: Color32) -> Shape {
+    let direction = if up { 1.0 } else { -1.0 };
+    let base_y = tip.y + direction * size;
+    let points = vec![
+        tip,
+        Pos2::new(tip.x - size / 2.0, base_y),
+        Pos2::new(tip.x + size / 2.0, base_y),
+    ];
+    Shape::convex_polygon(points, color, Stroke::none())
+}
+
 pub fn draw_octave(rect: Rect, active_notes: Vec<u8>) -> Vec<Shape> {
     let white = GLISS_THEME.piano.white;
     let black = GLISS_THEME.piano.black;
@@ -1,63 +1,129 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use egui::Ui;
 
+use crate::state::{ErrorState, PresetConfirm};
 use crate::EditorState;
 
-use anyhow::{Context, Result};
+use anyhow::Result;
+
+pub fn draw_preset_folder(ui: &mut Ui, state: &Arc<EditorState>) {
+    let mut browser = state.preset_browser.lock().unwrap();
+    let mut dir = browser.dir.display().to_string();
+    ui.label("Folder");
+    if ui
+        .add(egui::widgets::TextEdit::singleline(&mut dir).desired_width(200.0))
+        .changed()
+    {
+        browser.dir = PathBuf::from(dir);
+        browser.mark_stale();
+    }
+    ui.end_row();
+}
 
 pub fn draw_save_preset(ui: &mut Ui, state: &Arc<EditorState>) -> Result<()> {
     let button = ui.add(egui::widgets::Button::new("Save Preset"));
-    let mut filename = state.preset_filename.lock().unwrap();
-    ui.add(egui::widgets::TextEdit::singleline(&mut *filename).desired_width(75.0));
+    let mut filename = state.preset_filename.lock().unwrap().clone();
+    if ui
+        .add(egui::widgets::TextEdit::singleline(&mut filename).desired_width(75.0))
+        .changed()
+    {
+        *state.preset_filename.lock().unwrap() = filename.clone();
+    }
     ui.label(".preset");
+    // save_preset locks the browser, so the filename is not held here
     if button.clicked() {
-        let dir = dirs::home_dir().context("home directory not detected")?;
-        let tmp_dir = dir.join("tmp").join(format!("{filename}.preset"));
-        let parameter_file = std::fs::File::create(tmp_dir)?;
-        state.save_parameters(parameter_file)?;
+        state.save_preset(&filename, false)?;
     }
     ui.end_row();
     Ok(())
 }
 
 pub fn draw_load_preset(ui: &mut Ui, state: &Arc<EditorState>) -> Result<()> {
-    let f = |ui: &mut egui::Ui| {
-        let mut selected = String::new();
-        let log_folder = dirs::home_dir().context("home directory not detected")?;
-        let log_folder = log_folder.join("tmp");
-        let mut paths = vec![];
-        for element in std::fs::read_dir(log_folder)? {
-            let path = element?.path();
-            if let Some(extension) = path.extension() {
-                if extension == "preset" {
-                    paths.push(path);
+    let (presets, selected_text) = {
+        let mut browser = state.preset_browser.lock().unwrap();
+        let presets: Vec<(PathBuf, String)> = browser
+            .listing()?
+            .to_vec()
+            .into_iter()
+            .map(|path| {
+                let name = browser.name_of(&path);
+                (path, name)
+            })
+            .collect();
+        let selected_text = match &browser.loaded {
+            Some(path) => browser.name_of(path),
+            None => "Load Preset".to_string(),
+        };
+        (presets, selected_text)
+    };
+    let mut selected = None;
+    let combo_box = egui::ComboBox::from_label("")
+        // TODO take remaining horizontal space?
+        .width(200.0)
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            for (path, name) in presets {
+                if ui.selectable_label(false, name).clicked() {
+                    selected = Some(path);
                 }
             }
+        });
+    // presets saved outside the plugin show up once the list is opened again
+    if combo_box.response.clicked() {
+        state.preset_browser.lock().unwrap().mark_stale();
+    }
+    if let Some(path) = selected {
+        let report = state.load_preset(path)?;
+        if !report.is_complete() {
+            let mut error_state = state.error_state.lock().unwrap();
+            *error_state = Some(ErrorState::new(report.to_string()));
         }
-        for path in paths {
-            ui.selectable_value(&mut selected, path.display().to_string(), path.display());
-        }
+    }
+    Ok(())
+}
 
-        Ok(selected)
+// rename and delete act on the loaded preset, overwrite and delete ask first
+pub fn draw_manage_preset(ui: &mut Ui, state: &Arc<EditorState>) -> Result<()> {
+    let (loaded, confirm) = {
+        let browser = state.preset_browser.lock().unwrap();
+        (browser.loaded.clone(), browser.confirm.clone())
     };
-    if let Some(response) = egui::ComboBox::from_label("")
-        // TODO take remaining horizontal space?
-        .width(200.0)
-        .selected_text("Load Preset")
-        .show_ui(ui, f)
-        .inner
-    {
-        match response {
-            Ok(response) => {
-                if !response.is_empty() {
-                    let file = std::fs::File::open(response)?;
-                    state.load_parameters(file)?;
+    match confirm {
+        Some(PresetConfirm::Overwrite(path)) => {
+            let name = state.preset_browser.lock().unwrap().name_of(&path);
+            ui.label(format!("Overwrite {name}?"));
+            if ui.button("Yes").clicked() {
+                state.save_preset(&name, true)?;
+            }
+            if ui.button("No").clicked() {
+                state.preset_browser.lock().unwrap().confirm = None;
+            }
+        }
+        Some(PresetConfirm::Delete(path)) => {
+            let name = state.preset_browser.lock().unwrap().name_of(&path);
+            ui.label(format!("Delete {name}?"));
+            if ui.button("Yes").clicked() {
+                state.delete_preset(&path)?;
+            }
+            if ui.button("No").clicked() {
+                state.preset_browser.lock().unwrap().confirm = None;
+            }
+        }
+        None => {
+            if let Some(path) = loaded {
+                if ui.button("Rename").clicked() {
+                    let filename = state.preset_filename.lock().unwrap().clone();
+                    state.rename_preset(&path, &filename)?;
+                }
+                if ui.button("Delete").clicked() {
+                    state.preset_browser.lock().unwrap().confirm =
+                        Some(PresetConfirm::Delete(path));
                 }
-                return Ok(());
             }
-            Err(e) => return Err(e),
         }
     }
+    ui.end_row();
     Ok(())
 }
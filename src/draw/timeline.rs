@@ -3,8 +3,11 @@ use std::time::Duration;
 
 use std::sync::Arc;
 
-use crate::midi::Note;
-use crate::state::GlissParam::{BendDuration, HoldDuration};
+use crate::draw::piano::draw_arrow;
+use crate::midi::{note_name, pitch_label, Note};
+use crate::state::GlissParam::{
+    BendDuration, BendRate, FadeController, FadeDuration, FadeLevel, HoldDuration, SurplusVoices,
+};
 use crate::EditorState;
 
 use egui::{Color32, Id, Pos2, Rect, Sense, Shape, Stroke, Ui, Vec2};
@@ -24,14 +27,15 @@ pub struct Timeline {
 impl Timeline {
     pub fn new(
         rect: Rect,
-        octave_range: RangeInclusive<u8>,
+        octave_range: RangeInclusive<i8>,
         total_duration: Duration,
         bend_duration: Duration,
     ) -> Self {
         let to_screen =
             emath::RectTransform::from_to(Rect::from_x_y_ranges(0.0..=1.0, 0.0..=1.0), rect);
 
-        let midi_notes = ((octave_range.start() + 1) * 12)..((octave_range.end() + 2) * 12);
+        let midi_notes =
+            ((octave_range.start() + 1) as u8 * 12)..((octave_range.end() + 2) as u8 * 12);
         let n_notes = octave_range.len() * 12;
         let line_spacing = 1.0 / n_notes as f32;
         let p1 = to_screen * Pos2::new(0.0, line_spacing);
@@ -45,7 +49,51 @@ impl Timeline {
             line_spacing_absolute,
             total_duration,
             bend_duration,
-            history_duration: total_duration - bend_duration,
+            history_duration: total_duration.saturating_sub(bend_duration),
+        }
+    }
+
+    // midi number, with cents as the fraction, at a point on screen
+    fn midi_at(&self, pos: Pos2) -> f32 {
+        let y = self.to_screen.inverse().transform_pos(pos).y;
+        self.midi_notes.end as f32 - 0.5 - y / self.line_spacing
+    }
+
+    // note names down the left edge, and the pitch under the pointer
+    pub fn draw_pitch_rulers(&self, ui: &Ui, pointer: Option<Pos2>) {
+        let painter = ui.painter();
+        let color = Color32::from_additive_luminance(120);
+        // only label every C when the rows get too thin for text
+        let every_note = self.line_spacing_absolute >= 10.0;
+        for (i, midi_number) in self.midi_notes.clone().rev().enumerate() {
+            if every_note || midi_number % 12 == 0 {
+                let y = (i as f32 + 0.5) * self.line_spacing;
+                painter.text(
+                    self.to_screen * Pos2::new(0.0, y) + Vec2::new(2.0, 0.0),
+                    egui::Align2::LEFT_CENTER,
+                    note_name(midi_number),
+                    egui::TextStyle::Small,
+                    color,
+                );
+            }
+        }
+        let rect = self.to_screen.to();
+        if let Some(pointer) = pointer.filter(|pointer| rect.contains(*pointer)) {
+            let stroke = Stroke::new(0.5, Color32::WHITE);
+            painter.line_segment(
+                [
+                    Pos2::new(rect.min.x, pointer.y),
+                    Pos2::new(rect.max.x, pointer.y),
+                ],
+                stroke,
+            );
+            painter.text(
+                pointer + Vec2::new(8.0, -2.0),
+                egui::Align2::LEFT_BOTTOM,
+                pitch_label(self.midi_at(pointer)),
+                egui::TextStyle::Monospace,
+                Color32::WHITE,
+            );
         }
     }
 
@@ -55,17 +103,43 @@ impl Timeline {
         shapes.push(self.draw_vline());
 
         for note in recent_notes {
-            //if (self.midi_notes.start-1..=self.midi_notes.end+1).contains(&note.midi_number) {
             if self.midi_notes.contains(&note.midi_number) {
                 if let Some(whole_note) = self.draw_whole_note(now, note) {
                     shapes.push(whole_note);
                 }
-                // TODO implement else case
+            } else if let Some(arrow) = self.draw_offscreen_note(now, note) {
+                shapes.push(arrow);
             }
         }
         shapes
     }
 
+    // x in 0..1 where the note is drawn, None once it has scrolled out of the history
+    fn note_x(&self, now: Duration, note: &Note) -> Option<f32> {
+        let end_time = now.checked_sub(self.history_duration)?;
+        let draw_time: Duration = if note.new_note_on {
+            note.ui_time
+        } else {
+            note.ui_time + Duration::from_nanos(note.bend_duration as u64)
+        };
+        log::debug!("time debug - note.ui_time after adj: {:?}", note.ui_time);
+        let relative_time = draw_time.checked_sub(end_time)?;
+        Some(relative_time.div_duration_f32(self.total_duration))
+    }
+
+    // an arrow on the top or bottom edge for notes outside the key range
+    fn draw_offscreen_note(&self, now: Duration, note: Note) -> Option<Shape> {
+        let x = self.note_x(now, &note)?;
+        let above = note.midi_number >= self.midi_notes.end;
+        let tip = self.to_screen * Pos2::new(x, if above { 0.0 } else { 1.0 });
+        Some(draw_arrow(
+            tip,
+            above,
+            self.line_spacing_absolute.min(12.0),
+            Color32::WHITE,
+        ))
+    }
+
     fn draw_vline(&self) -> Shape {
         let x = 1.0 - self.bend_duration.div_duration_f32(self.total_duration);
         let p1 = self.to_screen * Pos2::new(x, 0.0);
@@ -89,29 +163,15 @@ impl Timeline {
     fn draw_whole_note(&self, now: Duration, note: Note) -> Option<Shape> {
         let relative_note = self.midi_notes.end - note.midi_number - 1;
         let stroke = Stroke::new(self.line_spacing_absolute / 7.5, Color32::WHITE);
-        if let Some(end_time) = now.checked_sub(self.history_duration) {
-            let draw_time: Duration = if note.new_note_on {
-                note.ui_time
-            } else {
-                note.ui_time + Duration::from_nanos(note.bend_duration as u64)
-            };
-            log::debug!("time debug - note.ui_time after adj: {:?}", note.ui_time);
-            if let Some(relative_time) = draw_time.checked_sub(end_time) {
-                let x = relative_time.div_duration_f32(self.total_duration);
-                let p1 = self.to_screen
-                    * Pos2::new(
-                        x,
-                        (relative_note as f32 * self.line_spacing) + (self.line_spacing / 2.0),
-                    );
-                let radius = (self.line_spacing_absolute / 2.0) - stroke.width;
-                let whole_note = Shape::circle_stroke(p1, radius, stroke);
-                Some(whole_note)
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+        let x = self.note_x(now, &note)?;
+        let p1 = self.to_screen
+            * Pos2::new(
+                x,
+                (relative_note as f32 * self.line_spacing) + (self.line_spacing / 2.0),
+            );
+        let radius = (self.line_spacing_absolute / 2.0) - stroke.width;
+        let whole_note = Shape::circle_stroke(p1, radius, stroke);
+        Some(whole_note)
     }
 
     pub fn draw_control_pin(&self, state: &Arc<EditorState>, ui: &mut Ui) {
@@ -162,7 +222,15 @@ impl Timeline {
         ui.painter().add(pin_circle_shape);
         if pin_response.dragged() {
             let mut editor_params = state.editor_params.lock().unwrap();
-            *editor_params = vec![BendDuration, HoldDuration];
+            *editor_params = vec![
+                BendDuration,
+                HoldDuration,
+                BendRate,
+                SurplusVoices,
+                FadeController,
+                FadeDuration,
+                FadeLevel,
+            ];
         }
         if pin_response.double_clicked() {
             state.set_parameter_to_default(BendDuration)
@@ -215,7 +283,15 @@ impl Timeline {
         ui.painter().add(pin_circle_shape);
         if pin_response.dragged() {
             let mut editor_params = state.editor_params.lock().unwrap();
-            *editor_params = vec![BendDuration, HoldDuration];
+            *editor_params = vec![
+                BendDuration,
+                HoldDuration,
+                BendRate,
+                SurplusVoices,
+                FadeController,
+                FadeDuration,
+                FadeLevel,
+            ];
         }
         if pin_response.double_clicked() {
             state.set_parameter_to_default(HoldDuration)
@@ -0,0 +1,262 @@
+use std::time::Instant;
+
+use rtrb::{Consumer, Producer, RingBuffer};
+use vst::event::MidiEvent;
+
+use crate::midi::bender::{Bender, RenderedBenders};
+use crate::midi::chord::{expire_chords, Chord, ChordBender, SurplusVoices, MAX_CHANNELS};
+use crate::midi::mapper::{ChordMap, MappingPreference, Unison, VoiceLeadingCost};
+use crate::midi::paths::BendPathBuilder;
+use crate::midi::voicing::{Doubling, VoicePriority};
+use crate::midi::ChannelVec;
+use crate::state::{EditorState, GlissParam};
+
+// while the editor is closed nobody drains the queue, once full no new updates are made
+const UPDATE_QUEUE_CAPACITY: usize = 1024;
+// a fade, a detune, an emerge and a note on for each new voice plus a note off for each old one
+const EVENTS_CAPACITY: usize = 8 * MAX_CHANNELS;
+
+// what the engine tells the ui, in the order it happened
+// kept inline so publishing never allocates on the audio thread
+#[allow(clippy::large_enum_variant)]
+pub enum EngineUpdate {
+    // the newest chord, each time a note joins or is released or it is sent to the bender
+    Chord(Chord),
+    // copies of the benders a chord retargeted, drawn on the ui thread
+    Benders(ChannelVec<Bender>),
+    Error(String),
+}
+
+// the ui's copy of what the engine is playing, rebuilt from the engine's updates
+#[derive(Default)]
+pub struct EngineView {
+    // None until an engine is connected
+    updates: Option<Consumer<EngineUpdate>>,
+    pub chords: Vec<Chord>,
+    pub rendered_benders: RenderedBenders,
+}
+
+impl EngineView {
+    // applies everything published since the last call, returning the newest error
+    pub fn update(&mut self) -> Option<String> {
+        let mut error = None;
+        let updates = self.updates.as_mut()?;
+        while let Ok(update) = updates.pop() {
+            match update {
+                EngineUpdate::Chord(chord) => match self.chords.last_mut() {
+                    Some(last) if last.start_time == chord.start_time => *last = chord,
+                    _ => {
+                        let start_time = chord.start_time;
+                        self.chords.push(chord);
+                        expire_chords(&mut self.chords, start_time);
+                    }
+                },
+                EngineUpdate::Benders(benders) => self
+                    .rendered_benders
+                    .append(benders.iter().map(Bender::get_render).collect()),
+                EngineUpdate::Error(message) => error = Some(message),
+            }
+        }
+        error
+    }
+}
+
+// the bender, owned by the thread the host calls process on, nothing it touches is locked by the ui
+// between chords a tick does not allocate, so it is safe to call from the audio callback
+pub struct Engine {
+    chord_bender: ChordBender,
+    updates: Producer<EngineUpdate>,
+    events: Vec<MidiEvent>,
+    // waits for room in the queue rather than being freed here
+    unsent_error: Option<String>,
+}
+
+impl Engine {
+    // replaces whatever engine the state's view was following
+    pub fn new(state: &EditorState) -> Self {
+        let (updates, view_updates) = RingBuffer::new(UPDATE_QUEUE_CAPACITY);
+        *state.engine_view.lock().unwrap() = EngineView {
+            updates: Some(view_updates),
+            ..Default::default()
+        };
+        let mut engine = Engine {
+            chord_bender: ChordBender::new(Instant::now(), 0.0, 0.0, 0.0, 0.0),
+            updates,
+            events: Vec::with_capacity(EVENTS_CAPACITY),
+            unsent_error: None,
+        };
+        engine.update_params(state);
+        engine
+    }
+
+    // only called with room in the queue, an update it rejected would be freed on this thread
+    fn publish(&mut self, update: EngineUpdate) {
+        debug_assert!(!self.updates.is_full());
+        let _ = self.updates.push(update);
+    }
+
+    // the ui catches up from the next chord
+    fn publish_chord(&mut self) {
+        if self.updates.is_full() {
+            return;
+        }
+        if let Some(chord) = self.chord_bender.chords.last() {
+            let chord = chord.clone();
+            self.publish(EngineUpdate::Chord(chord));
+        }
+    }
+
+    // notes go to the bender and control changes to midi learn, everything else is dropped
+    pub fn receive_midi(&mut self, state: &EditorState, event: MidiEvent, now: f64) {
+        match event.data[0] {
+            // note off and note on
+            128..=159 => {
+                self.chord_bender.push_event(event, now);
+                self.publish_chord();
+            }
+            // control change
+            176..=191 => state.receive_midi_cc(event.data[1], event.data[2]),
+            _ => (),
+        }
+    }
+
+    fn update_params(&mut self, state: &EditorState) {
+        let chord_bender = &mut self.chord_bender;
+        chord_bender.bend_duration = state.get_gliss_parameter(GlissParam::BendDuration);
+        chord_bender.bend_duration_randomness =
+            state.get_gliss_parameter(GlissParam::BendDurationRandomness);
+        chord_bender.bend_rate = state.get_gliss_parameter(GlissParam::BendRate);
+        chord_bender.hold_duration = state.get_gliss_parameter(GlissParam::HoldDuration);
+        chord_bender.pitch_bend_range =
+            state.get_gliss_parameter(GlissParam::PitchBendRange) as f32;
+        chord_bender.chord_capture_duration =
+            state.get_gliss_parameter(GlissParam::ChordCaptureDuration);
+        chord_bender.chord_mapper.chord_map =
+            ChordMap::from_f32(state.get_parameter(GlissParam::BendMapping));
+        chord_bender.chord_mapper.preference =
+            MappingPreference::from_f32(state.get_parameter(GlissParam::BendMappingPreference));
+        chord_bender.chord_mapper.cost =
+            VoiceLeadingCost::from_f32(state.get_parameter(GlissParam::OptimalMotion));
+        chord_bender.chord_mapper.prevent_crossings =
+            state.get_parameter(GlissParam::OptimalCrossings) >= 0.5;
+        chord_bender.chord_mapper.unison =
+            Unison::from_f32(state.get_parameter(GlissParam::UnisonDirection));
+        chord_bender.chord_mapper.unison_midi =
+            if state.get_parameter(GlissParam::UnisonPitch) < 0.5 {
+                None
+            } else {
+                Some(state.get_gliss_parameter(GlissParam::UnisonNote) as f32)
+            };
+        chord_bender.voicer.voice_count =
+            state.get_gliss_parameter(GlissParam::VoiceCount) as usize;
+        chord_bender.voicer.doubling =
+            Doubling::from_f32(state.get_parameter(GlissParam::VoiceDoubling));
+        chord_bender.voicer.detune_spread =
+            state.get_gliss_parameter(GlissParam::DetuneSpread) as f32;
+        chord_bender.voicer.priority =
+            VoicePriority::from_f32(state.get_parameter(GlissParam::VoicePriority));
+        chord_bender.surplus_voices =
+            SurplusVoices::from_f32(state.get_parameter(GlissParam::SurplusVoices));
+        chord_bender.fade_controller = if state.get_parameter(GlissParam::FadeController) < 0.5 {
+            7
+        } else {
+            11
+        };
+        chord_bender.fade_duration = state.get_gliss_parameter(GlissParam::FadeDuration);
+        chord_bender.fade_level = state.get_gliss_parameter(GlissParam::FadeLevel) as u8;
+        chord_bender.split_new_voices = state.get_parameter(GlissParam::NewVoices) >= 0.5;
+        chord_bender.bend_path = BendPathBuilder::from_state(state);
+    }
+
+    // one tick of the bender with the current params, shared by every plugin format and the standalone
+    pub fn bend(&mut self, state: &EditorState, now: f64) -> &[MidiEvent] {
+        self.update_params(state);
+        self.events.clear();
+        if !self.updates.is_full() {
+            if let Some(error) = self.unsent_error.take() {
+                self.publish(EngineUpdate::Error(error));
+            }
+        }
+        // room for the sent chord and the benders it retargeted
+        self.chord_bender.copy_retargeted = self.updates.slots() >= 2;
+        let unsent = |chord_bender: &ChordBender| {
+            chord_bender
+                .chords
+                .last()
+                .is_some_and(|chord| !chord.sent_to_bender)
+        };
+        let was_unsent = unsent(&self.chord_bender);
+        let result = self.chord_bender.bend(now, &mut self.events);
+        if was_unsent && !unsent(&self.chord_bender) {
+            self.publish_chord();
+        }
+        match result {
+            Ok(benders) => {
+                if !benders.is_empty() {
+                    self.publish(EngineUpdate::Benders(benders));
+                }
+                &self.events
+            }
+            Err(e) => {
+                match self.updates.is_full() {
+                    true => self.unsent_error = Some(e),
+                    false => self.publish(EngineUpdate::Error(e)),
+                }
+                &[]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod engine_updates {
+    use super::{Engine, UPDATE_QUEUE_CAPACITY};
+    use crate::midi::fixtures::{note_on, start_time, SECOND};
+    use crate::state::EditorState;
+
+    #[test]
+    fn the_view_follows_the_engine() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        let mut engine = Engine::new(&state);
+        let start = start_time();
+        for (idx, midi_number) in [60, 64, 67].iter().enumerate() {
+            engine.receive_midi(&state, note_on(*midi_number), start + idx as f64);
+        }
+        assert_eq!(state.update_engine_view().chords[0].notes.len(), 3);
+        assert!(!engine.bend(&state, start + SECOND).is_empty());
+        {
+            let view = state.update_engine_view();
+            assert_eq!(view.chords.len(), 1);
+            assert!(view.chords[0].sent_to_bender);
+            assert_eq!(view.rendered_benders.len(), view.chords[0].voices.len());
+        }
+
+        // a second engine on the same state starts the view over
+        let _engine = Engine::new(&state);
+        assert!(state.update_engine_view().chords.is_empty());
+    }
+
+    #[test]
+    fn a_full_queue_gets_no_new_updates() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        let mut engine = Engine::new(&state);
+        let start = start_time();
+        // every note on publishes the chord, nobody drains the queue
+        for idx in 0..UPDATE_QUEUE_CAPACITY {
+            engine.receive_midi(&state, note_on(60), start + idx as f64);
+        }
+        assert!(engine.updates.is_full());
+        assert!(!engine.bend(&state, start + SECOND).is_empty());
+        assert!(!engine.chord_bender.copy_retargeted);
+
+        // once drained the next chord gets through with its benders
+        assert!(state.update_engine_view().rendered_benders.is_empty());
+        engine.receive_midi(&state, note_on(64), start + 2.0 * SECOND);
+        assert!(!engine.bend(&state, start + 3.0 * SECOND).is_empty());
+        let view = state.update_engine_view();
+        assert!(view.chords.last().unwrap().sent_to_bender);
+        assert!(!view.rendered_benders.is_empty());
+    }
+}
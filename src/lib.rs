@@ -7,15 +7,23 @@ extern crate vst;
 #[macro_use]
 extern crate lazy_static;
 
+pub mod clap;
 pub mod draw;
+pub mod engine;
+pub mod lv2;
 pub mod midi;
 pub mod state;
+pub mod synth;
 pub mod ui;
 
-use crate::midi::mapper::ChordMap;
-use crate::midi::paths::BendPathBuilder;
-use crate::state::GlissParam::*;
-use crate::state::{EditorState, ErrorState, GLISS_PARAMETERS};
+#[cfg(test)]
+mod audio_scenario;
+#[cfg(test)]
+mod counting_allocator;
+
+use crate::engine::Engine;
+use crate::state::{EditorState, FACTORY_PRESETS, GLISS_PARAMETERS};
+use crate::synth::{PreviewSynth, Waveform, RENDER_CAPACITY};
 use crate::ui::GlissEditor;
 
 use std::sync::Arc;
@@ -24,7 +32,7 @@ use std::time::{Duration, SystemTime};
 use crate::vst::host::Host;
 use vst::buffer::{AudioBuffer, SendEventBuffer};
 use vst::editor::Editor;
-use vst::event::{Event, MidiEvent};
+use vst::event::Event;
 use vst::plugin::{CanDo, Category, HostCallback, Info, Plugin, PluginParameters};
 
 //pub const PITCH_BEND_RANGE: u8 = 48;
@@ -45,10 +53,12 @@ impl Nano {
 
 struct Gliss {
     state: Arc<EditorState>,
+    engine: Engine,
     editor: Option<GlissEditor>,
     host: HostCallback,
     send_buffer: SendEventBuffer,
-    events_buffer: Vec<vst::event::MidiEvent>,
+    preview_synth: PreviewSynth,
+    preview_buffer: Vec<f32>,
 }
 
 impl Default for Gliss {
@@ -58,29 +68,11 @@ impl Default for Gliss {
     }
 }
 
-fn get_note_events(events: &vst::api::Events) -> Vec<MidiEvent> {
-    let mut notes = vec![];
-    for e in events.events() {
-        if let Event::Midi(midi_event) = e {
-            match midi_event.data[0] {
-                // note off
-                128..=143 => notes.push(midi_event),
-                // note on
-                144..=159 => notes.push(midi_event),
-                _ => (),
-            }
-            //            if let 144..=159 = midi_event.data[0] {
-            //                notes.push(midi_event)
-            //            }
-        }
-    }
-    notes
-}
-
 impl Plugin for Gliss {
     fn new(host: HostCallback) -> Self {
         let state = Arc::new(EditorState::new());
         Self {
+            engine: Engine::new(&state),
             state: state.clone(),
             editor: Some(GlissEditor {
                 state,
@@ -89,7 +81,8 @@ impl Plugin for Gliss {
             }),
             host,
             send_buffer: SendEventBuffer::default(),
-            events_buffer: vec![],
+            preview_synth: PreviewSynth::new(),
+            preview_buffer: Vec::with_capacity(RENDER_CAPACITY),
         }
     }
 
@@ -105,6 +98,8 @@ impl Plugin for Gliss {
             parameters: GLISS_PARAMETERS.len() as i32,
             category: Category::Effect,
             midi_outputs: 1,
+            preset_chunks: true,
+            presets: FACTORY_PRESETS.len() as i32,
             ..Default::default()
         }
     }
@@ -139,9 +134,10 @@ impl Plugin for Gliss {
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
             .as_nanos() as f64;
-        let mut chord_bender = self.state.chord_bender.lock().unwrap();
-        for event in get_note_events(events) {
-            chord_bender.push_event(event, now);
+        for event in events.events() {
+            if let Event::Midi(midi_event) = event {
+                self.engine.receive_midi(&self.state, midi_event, now);
+            }
         }
     }
 
@@ -154,12 +150,15 @@ impl Plugin for Gliss {
         }
     }
 
-    fn process(&mut self, _buffer: &mut AudioBuffer<f32>) {
-        for (param, value) in self.state.params.iterate(true) {
-            self.host.automate(param as i32, value);
-        }
-        self.events_buffer = vec![];
+    fn set_sample_rate(&mut self, rate: f32) {
+        self.preview_synth.sample_rate = rate;
+    }
 
+    fn set_block_size(&mut self, size: i64) {
+        self.preview_buffer.reserve(size as usize);
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
         //        let time_info = self.host.get_time_info(1).unwrap();
         //        let host_time = time_info.nanoseconds;
         let host_time = SystemTime::now()
@@ -167,32 +166,14 @@ impl Plugin for Gliss {
             .unwrap()
             .as_nanos() as f64;
 
-        let mut chord_bender = self.state.chord_bender.lock().unwrap();
-        chord_bender.bend_duration = self.state.get_gliss_parameter(BendDuration);
-        chord_bender.hold_duration = self.state.get_gliss_parameter(HoldDuration);
-        chord_bender.pitch_bend_range = self.state.get_gliss_parameter(PitchBendRange) as f32;
-        chord_bender.chord_capture_duration = self.state.get_gliss_parameter(ChordCaptureDuration);
-        chord_bender.chord_mapper.chord_map =
-            ChordMap::from_f32(self.state.get_parameter(BendMapping));
-        chord_bender.bend_path = BendPathBuilder::from_state(&self.state);
-
-        match chord_bender.bend(host_time) {
-            Ok((events, new_rendered_benders)) => {
-                let mut rendered_benders = self.state.rendered_benders.lock().unwrap();
-                // TODO use new method
-                rendered_benders.append(new_rendered_benders);
-
-                log::debug!(
-                    "sending events: {:?}",
-                    events.iter().map(|e| e.data).collect::<Vec<[u8; 3]>>()
-                );
-                self.send_buffer.send_events(&events, &mut self.host);
-            }
-            Err(e) => {
-                let mut error_state = self.state.error_state.lock().unwrap();
-                *error_state = Some(ErrorState::new(e));
+        let mut host = self.host;
+        let samples = buffer.samples();
+        let (_, mut outputs) = buffer.split();
+        self.process_block(&mut host, host_time, samples, |offset, chunk| {
+            for channel in 0..outputs.len() {
+                outputs.get_mut(channel)[offset..offset + chunk.len()].copy_from_slice(chunk);
             }
-        }
+        });
     }
 
     fn get_parameter_object(&mut self) -> Arc<dyn PluginParameters> {
@@ -220,4 +201,172 @@ impl Plugin for Gliss {
     }
 }
 
+impl Gliss {
+    // one block at the given time, the preview synth is written out a chunk at a time
+    // tests pass a simulated clock and their own host, returns the number of midi events sent
+    fn process_block(
+        &mut self,
+        host: &mut impl Host,
+        host_time: f64,
+        samples: usize,
+        write: impl FnMut(usize, &[f32]),
+    ) -> usize {
+        let host_edits = self.state.take_host_edits();
+        for edit in host_edits.begun() {
+            host.begin_edit(edit.index());
+        }
+        for (param, value) in self.state.params.iterate(true) {
+            host.automate(param as i32, value);
+        }
+        for edit in host_edits.ended() {
+            host.end_edit(edit.index());
+        }
+
+        let events = self.engine.bend(&self.state, host_time);
+        log::debug!(
+            "sending events: {:?}",
+            events.iter().map(|e| e.data).collect::<Vec<[u8; 3]>>()
+        );
+        self.send_buffer.send_events(events, host);
+
+        self.state.update_preview_synth(&mut self.preview_synth);
+        for event in events.iter() {
+            self.preview_synth.handle_event(event.data);
+        }
+        if self.preview_synth.waveform != Waveform::Off {
+            self.preview_synth
+                .render_chunks(&mut self.preview_buffer, samples, write);
+        }
+        events.len()
+    }
+}
+
 plugin_main!(Gliss);
+
+#[cfg(test)]
+mod vst2_process {
+    use super::*;
+    use crate::audio_scenario::{bends_without_allocating, PluginFormat, BLOCK_SIZE, SAMPLE_RATE};
+    use crate::counting_allocator::allocations;
+    use crate::midi::fixtures::{note_on, start_time};
+    use crate::state::GlissParam;
+    use std::cell::Cell;
+
+    // counts what process tells the host
+    #[derive(Default)]
+    struct CountingHost {
+        begun: Cell<usize>,
+        automated: Cell<usize>,
+        ended: Cell<usize>,
+    }
+
+    impl Host for CountingHost {
+        fn begin_edit(&self, _index: i32) {
+            self.begun.set(self.begun.get() + 1);
+        }
+
+        fn automate(&self, _index: i32, _value: f32) {
+            self.automated.set(self.automated.get() + 1);
+        }
+
+        fn end_edit(&self, _index: i32) {
+            self.ended.set(self.ended.get() + 1);
+        }
+    }
+
+    // drives process_block the way process does, notes go straight to the engine
+    struct Vst2 {
+        gliss: Gliss,
+        host: CountingHost,
+        notes: Vec<u8>,
+        output: Vec<f32>,
+    }
+
+    impl PluginFormat for Vst2 {
+        fn state(&self) -> &EditorState {
+            &self.gliss.state
+        }
+
+        fn set_param(&mut self, param: GlissParam, plain: f64) {
+            let index = GLISS_PARAMETERS.iter().position(|p| *p == param).unwrap();
+            self.gliss
+                .state
+                .params
+                .set_parameter(index, param.plain_to_daw(plain));
+        }
+
+        fn send_notes(&mut self, midi_numbers: &[u8]) {
+            self.notes.extend_from_slice(midi_numbers);
+        }
+
+        fn process(&mut self, now: f64) -> usize {
+            let Vst2 {
+                gliss,
+                host,
+                notes,
+                output,
+            } = self;
+            for midi_number in notes.drain(..) {
+                gliss
+                    .engine
+                    .receive_midi(&gliss.state, note_on(midi_number), now);
+            }
+            gliss.process_block(host, now, BLOCK_SIZE, |offset, chunk| {
+                output[offset..offset + chunk.len()].copy_from_slice(chunk)
+            })
+        }
+
+        fn output(&self) -> &[f32] {
+            &self.output
+        }
+    }
+
+    #[test]
+    fn bending_runs_without_allocating() {
+        // init without its log file
+        let mut gliss = Gliss::new(HostCallback::default());
+        gliss.state.set_parameters_to_default();
+        gliss.set_sample_rate(SAMPLE_RATE);
+        gliss.set_block_size(BLOCK_SIZE as i64);
+        let mut vst2 = Vst2 {
+            gliss,
+            host: CountingHost::default(),
+            notes: Vec::with_capacity(16),
+            output: vec![0.0; BLOCK_SIZE],
+        };
+        bends_without_allocating(&mut vst2);
+        // the ui gesture reaches the host
+        let host = &vst2.host;
+        assert_eq!((host.begun.get(), host.ended.get()), (1, 1));
+        assert!(host.automated.get() >= 2);
+    }
+
+    #[test]
+    fn blocks_longer_than_the_reserve_render_in_full() {
+        let mut gliss = Gliss::new(HostCallback::default());
+        gliss.state.set_parameters_to_default();
+        gliss.set_sample_rate(SAMPLE_RATE);
+        let mut vst2 = Vst2 {
+            gliss,
+            host: CountingHost::default(),
+            notes: vec![],
+            output: vec![0.0; 3 * RENDER_CAPACITY],
+        };
+        vst2.set_param(GlissParam::PreviewWaveform, 1.0);
+        vst2.gliss.preview_synth.handle_event([144, 60, 127]);
+        let Vst2 {
+            gliss,
+            host,
+            output,
+            ..
+        } = &mut vst2;
+        let before = allocations();
+        gliss.process_block(host, start_time(), output.len(), |offset, chunk| {
+            output[offset..offset + chunk.len()].copy_from_slice(chunk)
+        });
+        assert_eq!(allocations() - before, 0);
+        assert!(output[2 * RENDER_CAPACITY..]
+            .iter()
+            .any(|sample| *sample != 0.0));
+    }
+}
@@ -0,0 +1,710 @@
+// the same engine as the VST2 plugin as an LV2 plugin, the bundle's ttl comes from write_bundle
+#![allow(non_camel_case_types)]
+
+use std::ffi::CStr;
+use std::fs;
+use std::os::raw::{c_char, c_void};
+use std::path::Path;
+use std::ptr;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use vst::event::MidiEvent;
+
+use crate::engine::Engine;
+use crate::state::{EditorState, GlissParam, GLISS_PARAMETERS};
+use crate::synth::PreviewSynth;
+
+pub const PLUGIN_URI: &str = "https://joshuapostel.github.io/deep-gliss/lv2";
+const PLUGIN_URI_C: &[u8] = b"https://joshuapostel.github.io/deep-gliss/lv2\0";
+const URID_MAP_URI: &[u8] = b"http://lv2plug.in/ns/ext/urid#map\0";
+const MIDI_EVENT_URI: &[u8] = b"http://lv2plug.in/ns/ext/midi#MidiEvent\0";
+const ATOM_SEQUENCE_URI: &[u8] = b"http://lv2plug.in/ns/ext/atom#Sequence\0";
+
+// ports before the controls, which follow in GLISS_PARAMETERS order
+const MIDI_IN_PORT: u32 = 0;
+const MIDI_OUT_PORT: u32 = 1;
+const AUDIO_OUT_PORTS: [u32; 2] = [2, 3];
+const FIRST_CONTROL_PORT: u32 = 4;
+
+type LV2_Handle = *mut c_void;
+type LV2_URID = u32;
+
+#[repr(C)]
+pub struct LV2_Feature {
+    pub uri: *const c_char,
+    pub data: *mut c_void,
+}
+
+#[repr(C)]
+pub struct LV2_URID_Map {
+    pub handle: *mut c_void,
+    pub map: unsafe extern "C" fn(handle: *mut c_void, uri: *const c_char) -> LV2_URID,
+}
+
+#[repr(C)]
+pub struct LV2_Descriptor {
+    pub uri: *const c_char,
+    pub instantiate: unsafe extern "C" fn(
+        descriptor: *const LV2_Descriptor,
+        sample_rate: f64,
+        bundle_path: *const c_char,
+        features: *const *const LV2_Feature,
+    ) -> LV2_Handle,
+    pub connect_port: unsafe extern "C" fn(instance: LV2_Handle, port: u32, data: *mut c_void),
+    pub activate: unsafe extern "C" fn(instance: LV2_Handle),
+    pub run: unsafe extern "C" fn(instance: LV2_Handle, sample_count: u32),
+    pub deactivate: unsafe extern "C" fn(instance: LV2_Handle),
+    pub cleanup: unsafe extern "C" fn(instance: LV2_Handle),
+    pub extension_data: unsafe extern "C" fn(uri: *const c_char) -> *const c_void,
+}
+
+// only ever points at a static string
+unsafe impl Sync for LV2_Descriptor {}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LV2_Atom {
+    pub size: u32,
+    pub type_: u32,
+}
+
+#[repr(C)]
+pub struct LV2_Atom_Sequence {
+    pub atom: LV2_Atom,
+    pub unit: u32,
+    pub pad: u32,
+}
+
+// followed by body.size bytes, the next event starts 8 byte aligned
+#[repr(C)]
+pub struct LV2_Atom_Event {
+    pub frames: i64,
+    pub body: LV2_Atom,
+}
+
+static DESCRIPTOR: LV2_Descriptor = LV2_Descriptor {
+    uri: PLUGIN_URI_C.as_ptr() as *const c_char,
+    instantiate,
+    connect_port,
+    activate,
+    run,
+    deactivate,
+    cleanup,
+    extension_data,
+};
+
+#[no_mangle]
+pub extern "C" fn lv2_descriptor(index: u32) -> *const LV2_Descriptor {
+    if index == 0 {
+        &DESCRIPTOR
+    } else {
+        ptr::null()
+    }
+}
+
+struct Lv2Gliss {
+    state: EditorState,
+    engine: Engine,
+    preview_synth: PreviewSynth,
+    midi_event: LV2_URID,
+    atom_sequence: LV2_URID,
+    midi_in: *const LV2_Atom_Sequence,
+    midi_out: *mut LV2_Atom_Sequence,
+    audio_out: [*mut f32; 2],
+    controls: Vec<*const f32>,
+    // a control is applied when it moves, so midi learn can change params between moves
+    last_controls: Vec<f32>,
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as f64
+}
+
+fn pad_8(size: u32) -> u32 {
+    (size + 7) & !7
+}
+
+unsafe fn find_urid_map(features: *const *const LV2_Feature) -> Option<&'static LV2_URID_Map> {
+    if features.is_null() {
+        return None;
+    }
+    let mut feature = features;
+    while !(*feature).is_null() {
+        if CStr::from_ptr((**feature).uri).to_bytes_with_nul() == URID_MAP_URI {
+            return Some(&*((**feature).data as *const LV2_URID_Map));
+        }
+        feature = feature.add(1);
+    }
+    None
+}
+
+unsafe extern "C" fn instantiate(
+    _descriptor: *const LV2_Descriptor,
+    sample_rate: f64,
+    _bundle_path: *const c_char,
+    features: *const *const LV2_Feature,
+) -> LV2_Handle {
+    let urid_map = match find_urid_map(features) {
+        Some(urid_map) => urid_map,
+        None => return ptr::null_mut(),
+    };
+    let map = |uri: &[u8]| (urid_map.map)(urid_map.handle, uri.as_ptr() as *const c_char);
+    let state = EditorState::new();
+    state.set_parameters_to_default();
+    let mut preview_synth = PreviewSynth::new();
+    preview_synth.sample_rate = sample_rate as f32;
+    let gliss = Box::new(Lv2Gliss {
+        engine: Engine::new(&state),
+        state,
+        preview_synth,
+        midi_event: map(MIDI_EVENT_URI),
+        atom_sequence: map(ATOM_SEQUENCE_URI),
+        midi_in: ptr::null(),
+        midi_out: ptr::null_mut(),
+        audio_out: [ptr::null_mut(); 2],
+        controls: vec![ptr::null(); GLISS_PARAMETERS.len()],
+        last_controls: vec![f32::NAN; GLISS_PARAMETERS.len()],
+    });
+    Box::into_raw(gliss) as LV2_Handle
+}
+
+unsafe extern "C" fn connect_port(instance: LV2_Handle, port: u32, data: *mut c_void) {
+    let gliss = &mut *(instance as *mut Lv2Gliss);
+    match port {
+        MIDI_IN_PORT => gliss.midi_in = data as *const LV2_Atom_Sequence,
+        MIDI_OUT_PORT => gliss.midi_out = data as *mut LV2_Atom_Sequence,
+        port if AUDIO_OUT_PORTS.contains(&port) => {
+            gliss.audio_out[(port - AUDIO_OUT_PORTS[0]) as usize] = data as *mut f32
+        }
+        port => {
+            if let Some(control) = gliss.controls.get_mut((port - FIRST_CONTROL_PORT) as usize) {
+                *control = data as *const f32;
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn activate(_instance: LV2_Handle) {}
+
+unsafe extern "C" fn run(instance: LV2_Handle, sample_count: u32) {
+    run_at(&mut *(instance as *mut Lv2Gliss), sample_count, now());
+}
+
+// one block at the given time, tests pass a simulated clock
+unsafe fn run_at(gliss: &mut Lv2Gliss, sample_count: u32, now: f64) {
+    for (index, (control, last)) in gliss
+        .controls
+        .iter()
+        .zip(gliss.last_controls.iter_mut())
+        .enumerate()
+    {
+        if control.is_null() || **control == *last {
+            continue;
+        }
+        *last = **control;
+        let daw_value = GLISS_PARAMETERS[index].plain_to_daw(**control as f64);
+        gliss.state.params.set_parameter(index, daw_value);
+    }
+
+    if !gliss.midi_in.is_null() {
+        let sequence = gliss.midi_in;
+        let body = (sequence as *const u8).add(std::mem::size_of::<LV2_Atom>());
+        // a sequence's body starts with 8 bytes of unit and pad, hosts may send less when empty
+        let events_size = ((*sequence).atom.size as usize).saturating_sub(8);
+        let mut offset = 0;
+        while offset + std::mem::size_of::<LV2_Atom_Event>() <= events_size {
+            let event = body.add(8 + offset) as *const LV2_Atom_Event;
+            let size = (*event).body.size;
+            if (*event).body.type_ == gliss.midi_event && size >= 2 {
+                let data = (event as *const u8).add(std::mem::size_of::<LV2_Atom_Event>());
+                let mut midi = [0u8; 3];
+                ptr::copy_nonoverlapping(data, midi.as_mut_ptr(), (size as usize).min(3));
+                gliss
+                    .engine
+                    .receive_midi(&gliss.state, midi_event(midi), now);
+            }
+            offset += pad_8(std::mem::size_of::<LV2_Atom_Event>() as u32 + size) as usize;
+        }
+    }
+
+    let events = gliss.engine.bend(&gliss.state, now);
+    if !gliss.midi_out.is_null() {
+        let sequence = gliss.midi_out;
+        // the host sets the size to the buffer's capacity
+        let capacity = (*sequence).atom.size;
+        (*sequence).atom.type_ = gliss.atom_sequence;
+        (*sequence).atom.size = 8;
+        (*sequence).unit = 0;
+        (*sequence).pad = 0;
+        let event_size = pad_8(std::mem::size_of::<LV2_Atom_Event>() as u32 + 3);
+        for event in events.iter() {
+            if (*sequence).atom.size + event_size > capacity {
+                log::warn!("lv2 midi out is full, dropping {:?}", event.data);
+                break;
+            }
+            let out = (sequence as *mut u8)
+                .add(std::mem::size_of::<LV2_Atom>() + (*sequence).atom.size as usize)
+                as *mut LV2_Atom_Event;
+            (*out).frames = 0;
+            (*out).body = LV2_Atom {
+                size: 3,
+                type_: gliss.midi_event,
+            };
+            let data = (out as *mut u8).add(std::mem::size_of::<LV2_Atom_Event>());
+            ptr::copy_nonoverlapping(event.data.as_ptr(), data, 3);
+            (*sequence).atom.size += event_size;
+        }
+    }
+
+    gliss.state.update_preview_synth(&mut gliss.preview_synth);
+    for event in events.iter() {
+        gliss.preview_synth.handle_event(event.data);
+    }
+    let [left, right] = gliss.audio_out;
+    if !left.is_null() {
+        let left = std::slice::from_raw_parts_mut(left, sample_count as usize);
+        left.iter_mut().for_each(|sample| *sample = 0.0);
+        gliss.preview_synth.render(left);
+        if !right.is_null() {
+            std::slice::from_raw_parts_mut(right, sample_count as usize).copy_from_slice(left);
+        }
+    }
+}
+
+unsafe extern "C" fn deactivate(_instance: LV2_Handle) {}
+
+unsafe extern "C" fn cleanup(instance: LV2_Handle) {
+    drop(Box::from_raw(instance as *mut Lv2Gliss));
+}
+
+unsafe extern "C" fn extension_data(_uri: *const c_char) -> *const c_void {
+    ptr::null()
+}
+
+fn midi_event(data: [u8; 3]) -> MidiEvent {
+    MidiEvent {
+        data,
+        delta_frames: 0,
+        live: true,
+        note_length: None,
+        note_offset: None,
+        detune: 0,
+        note_off_velocity: 0,
+    }
+}
+
+// BendDuration becomes bend_duration
+fn port_symbol(param: GlissParam) -> String {
+    let mut symbol = String::new();
+    for (idx, c) in format!("{param:?}").chars().enumerate() {
+        if c.is_ascii_uppercase() && idx > 0 {
+            symbol.push('_');
+        }
+        symbol.push(c.to_ascii_lowercase());
+    }
+    symbol
+}
+
+fn control_port_ttl(index: u32, param: GlissParam) -> String {
+    let config = param.get_config();
+    let mut ttl = format!(
+        "    [
+        a lv2:InputPort, lv2:ControlPort ;
+        lv2:index {} ;
+        lv2:symbol \"{}\" ;
+        lv2:name \"{}\" ;
+        lv2:default {:?} ;
+        lv2:minimum {:?} ;
+        lv2:maximum {:?} ;
+",
+        index,
+        port_symbol(param),
+        config.daw_name,
+        param.daw_to_plain(config.map_to_daw(config.default)),
+        param.daw_to_plain(0.0),
+        param.daw_to_plain(1.0),
+    );
+    match param.option_count() {
+        Some(n_options) => {
+            ttl.push_str("        lv2:portProperty lv2:integer, lv2:enumeration ;\n");
+            for option_idx in 0..n_options {
+                let option = param
+                    .option_name(param.option_daw_value(option_idx))
+                    .expect("categorical");
+                ttl.push_str(&format!(
+                    "        lv2:scalePoint [ rdfs:label \"{option}\" ; rdf:value {option_idx} ] ;\n"
+                ));
+            }
+        }
+        None if config.is_integer => {
+            ttl.push_str("        lv2:portProperty lv2:integer ;\n");
+        }
+        None => (),
+    }
+    let unit = match config.unit {
+        "seconds" => Some("units:s"),
+        "semitones" => Some("units:semitone12TET"),
+        "cents" => Some("units:cent"),
+        "midi" => Some("units:midiNote"),
+        _ => None,
+    };
+    if let Some(unit) = unit {
+        ttl.push_str(&format!("        units:unit {unit} ;\n"));
+    }
+    ttl.push_str("    ]");
+    ttl
+}
+
+pub fn plugin_ttl() -> String {
+    let mut ports = vec![
+        format!(
+            "    [
+        a lv2:InputPort, atom:AtomPort ;
+        atom:bufferType atom:Sequence ;
+        atom:supports midi:MidiEvent ;
+        lv2:designation lv2:control ;
+        lv2:index {MIDI_IN_PORT} ;
+        lv2:symbol \"midi_in\" ;
+        lv2:name \"Chords In\" ;
+    ]"
+        ),
+        format!(
+            "    [
+        a lv2:OutputPort, atom:AtomPort ;
+        atom:bufferType atom:Sequence ;
+        atom:supports midi:MidiEvent ;
+        lv2:index {MIDI_OUT_PORT} ;
+        lv2:symbol \"midi_out\" ;
+        lv2:name \"Glissandi Out\" ;
+    ]"
+        ),
+    ];
+    for (port, (symbol, name)) in AUDIO_OUT_PORTS.iter().zip([
+        ("preview_left", "Preview Left"),
+        ("preview_right", "Preview Right"),
+    ]) {
+        ports.push(format!(
+            "    [
+        a lv2:OutputPort, lv2:AudioPort ;
+        lv2:index {port} ;
+        lv2:symbol \"{symbol}\" ;
+        lv2:name \"{name}\" ;
+    ]"
+        ));
+    }
+    for (index, param) in GLISS_PARAMETERS.iter().enumerate() {
+        ports.push(control_port_ttl(FIRST_CONTROL_PORT + index as u32, *param));
+    }
+    format!(
+        "@prefix atom: <http://lv2plug.in/ns/ext/atom#> .
+@prefix doap: <http://usefulinc.com/ns/doap#> .
+@prefix lv2: <http://lv2plug.in/ns/lv2core#> .
+@prefix midi: <http://lv2plug.in/ns/ext/midi#> .
+@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+@prefix units: <http://lv2plug.in/ns/extensions/units#> .
+@prefix urid: <http://lv2plug.in/ns/ext/urid#> .
+
+<{PLUGIN_URI}>
+    a lv2:Plugin, lv2:MIDIPlugin ;
+    doap:name \"DeepGliss\" ;
+    doap:license <https://opensource.org/licenses/MIT> ;
+    lv2:requiredFeature urid:map ;
+    lv2:port
+{} .
+",
+        ports.join(" ,\n")
+    )
+}
+
+pub fn manifest_ttl(library: &str) -> String {
+    format!(
+        "@prefix lv2: <http://lv2plug.in/ns/lv2core#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+
+<{PLUGIN_URI}>
+    a lv2:Plugin ;
+    lv2:binary <{library}> ;
+    rdfs:seeAlso <DeepGliss.ttl> .
+"
+    )
+}
+
+// writes the ttl next to a copy of the built library
+pub fn write_bundle(bundle: &Path, library: &Path) -> Result<()> {
+    fs::create_dir_all(bundle)?;
+    let library_name = library
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("libgliss.so");
+    fs::copy(library, bundle.join(library_name))?;
+    fs::write(bundle.join("manifest.ttl"), manifest_ttl(library_name))?;
+    fs::write(bundle.join("DeepGliss.ttl"), plugin_ttl())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod lv2_plugin {
+    use super::*;
+    use crate::audio_scenario::{bends_without_allocating, PluginFormat, BLOCK_SIZE, SAMPLE_RATE};
+    use std::collections::HashMap;
+
+    unsafe extern "C" fn map_uri(handle: *mut c_void, uri: *const c_char) -> LV2_URID {
+        let uris = &mut *(handle as *mut HashMap<String, LV2_URID>);
+        let uri = CStr::from_ptr(uri).to_string_lossy().to_string();
+        let next = uris.len() as LV2_URID + 1;
+        *uris.entry(uri).or_insert(next)
+    }
+
+    // 8 byte aligned like host atom buffers
+    fn atom_buffer(capacity: usize) -> Vec<u64> {
+        vec![0; capacity / 8]
+    }
+
+    #[test]
+    fn ttl_has_a_port_per_param() {
+        let ttl = plugin_ttl();
+        let last_index = FIRST_CONTROL_PORT as usize + GLISS_PARAMETERS.len() - 1;
+        assert!(ttl.contains(&format!("lv2:index {last_index} ;")));
+        assert!(ttl.contains("lv2:symbol \"bend_duration\""));
+        assert!(ttl.contains("rdfs:label \"Saw\" ; rdf:value 2"));
+        assert!(ttl.contains("rdfs:label \"Optimal\" ; rdf:value 3"));
+        assert!(ttl.contains("rdfs:label \"SCurve\" ; rdf:value 0"));
+        assert!(!ttl.contains("hardRTCapable"));
+        assert_eq!(
+            ttl.matches("lv2:ControlPort").count(),
+            GLISS_PARAMETERS.len()
+        );
+    }
+
+    #[test]
+    fn controls_and_midi_learn_through_ports() {
+        let mut uris: HashMap<String, LV2_URID> = HashMap::new();
+        let mut map = LV2_URID_Map {
+            handle: &mut uris as *mut HashMap<String, LV2_URID> as *mut c_void,
+            map: map_uri,
+        };
+        let feature = LV2_Feature {
+            uri: URID_MAP_URI.as_ptr() as *const c_char,
+            data: &mut map as *mut LV2_URID_Map as *mut c_void,
+        };
+        let features = [&feature as *const LV2_Feature, ptr::null()];
+        unsafe {
+            let descriptor = &*lv2_descriptor(0);
+            let instance =
+                (descriptor.instantiate)(descriptor, 48_000.0, ptr::null(), features.as_ptr());
+            assert!(!instance.is_null());
+
+            let mut controls: Vec<f32> = GLISS_PARAMETERS
+                .iter()
+                .map(|param| {
+                    let config = param.get_config();
+                    param.daw_to_plain(config.map_to_daw(config.default)) as f32
+                })
+                .collect();
+            let hold_duration = GLISS_PARAMETERS
+                .iter()
+                .position(|param| *param == GlissParam::HoldDuration)
+                .unwrap();
+            controls[hold_duration] = 5.0;
+            for (index, control) in controls.iter_mut().enumerate() {
+                (descriptor.connect_port)(
+                    instance,
+                    FIRST_CONTROL_PORT + index as u32,
+                    control as *mut f32 as *mut c_void,
+                );
+            }
+
+            // one CC 20 event after a learn
+            let mut midi_in = atom_buffer(64);
+            let sequence = midi_in.as_mut_ptr() as *mut LV2_Atom_Sequence;
+            let midi_event = uris["http://lv2plug.in/ns/ext/midi#MidiEvent"];
+            (*sequence).atom.size = 8 + 24;
+            let event = (sequence as *mut u8).add(16) as *mut LV2_Atom_Event;
+            (*event).body = LV2_Atom {
+                size: 3,
+                type_: midi_event,
+            };
+            let data = (event as *mut u8).add(16);
+            ptr::copy_nonoverlapping([176u8, 20, 0].as_ptr(), data, 3);
+            (descriptor.connect_port)(instance, MIDI_IN_PORT, midi_in.as_mut_ptr() as *mut c_void);
+
+            let mut midi_out = atom_buffer(1024);
+            let out_sequence = midi_out.as_mut_ptr() as *mut LV2_Atom_Sequence;
+            (*out_sequence).atom.size = 1024 - 8;
+            (descriptor.connect_port)(instance, MIDI_OUT_PORT, out_sequence as *mut c_void);
+
+            let gliss = &*(instance as *const Lv2Gliss);
+            gliss.state.learn_midi(GlissParam::BendDuration);
+            (descriptor.run)(instance, 64);
+
+            assert!((gliss.state.get_ui_parameter(GlissParam::HoldDuration) - 5.0).abs() < 0.0001);
+            let min = GlissParam::BendDuration.get_config().min;
+            assert!((gliss.state.get_ui_parameter(GlissParam::BendDuration) - min).abs() < 0.0001);
+            assert_eq!((*out_sequence).atom.size, 8);
+            assert_eq!(
+                (*out_sequence).atom.type_,
+                uris["http://lv2plug.in/ns/ext/atom#Sequence"]
+            );
+
+            // an atom with no sequence body at all
+            (*sequence).atom.size = 0;
+            (*out_sequence).atom.size = 1024 - 8;
+            (descriptor.run)(instance, 64);
+            assert_eq!((*out_sequence).atom.size, 8);
+
+            (descriptor.cleanup)(instance);
+        }
+    }
+
+    // fills an 8 byte aligned buffer with a sequence of 3 byte midi events
+    unsafe fn write_midi(buffer: &mut [u64], midi_event: LV2_URID, events: &[[u8; 3]]) {
+        let sequence = buffer.as_mut_ptr() as *mut LV2_Atom_Sequence;
+        let event_size = pad_8(std::mem::size_of::<LV2_Atom_Event>() as u32 + 3);
+        (*sequence).atom.size = 8 + event_size * events.len() as u32;
+        for (idx, data) in events.iter().enumerate() {
+            let event =
+                (sequence as *mut u8).add(16 + idx * event_size as usize) as *mut LV2_Atom_Event;
+            (*event).frames = 0;
+            (*event).body = LV2_Atom {
+                size: 3,
+                type_: midi_event,
+            };
+            let body = (event as *mut u8).add(std::mem::size_of::<LV2_Atom_Event>());
+            ptr::copy_nonoverlapping(data.as_ptr(), body, 3);
+        }
+    }
+
+    // an instance with every port connected, driven through run_at with the scenario's clock
+    struct Lv2 {
+        instance: LV2_Handle,
+        midi_event: LV2_URID,
+        controls: Vec<f32>,
+        left: Vec<f32>,
+        right: Vec<f32>,
+        midi_in: Vec<u64>,
+        midi_out: Vec<u64>,
+    }
+
+    impl Lv2 {
+        unsafe fn new() -> Self {
+            let mut uris: HashMap<String, LV2_URID> = HashMap::new();
+            let mut map = LV2_URID_Map {
+                handle: &mut uris as *mut HashMap<String, LV2_URID> as *mut c_void,
+                map: map_uri,
+            };
+            let feature = LV2_Feature {
+                uri: URID_MAP_URI.as_ptr() as *const c_char,
+                data: &mut map as *mut LV2_URID_Map as *mut c_void,
+            };
+            let features = [&feature as *const LV2_Feature, ptr::null()];
+            let descriptor = &*lv2_descriptor(0);
+            let instance = (descriptor.instantiate)(
+                descriptor,
+                SAMPLE_RATE as f64,
+                ptr::null(),
+                features.as_ptr(),
+            );
+            assert!(!instance.is_null());
+            let mut lv2 = Lv2 {
+                instance,
+                midi_event: uris["http://lv2plug.in/ns/ext/midi#MidiEvent"],
+                controls: GLISS_PARAMETERS
+                    .iter()
+                    .map(|param| {
+                        let config = param.get_config();
+                        param.daw_to_plain(config.map_to_daw(config.default)) as f32
+                    })
+                    .collect(),
+                left: vec![0.0; BLOCK_SIZE],
+                right: vec![0.0; BLOCK_SIZE],
+                midi_in: atom_buffer(128),
+                midi_out: atom_buffer(1024),
+            };
+            for (index, control) in lv2.controls.iter_mut().enumerate() {
+                (descriptor.connect_port)(
+                    instance,
+                    FIRST_CONTROL_PORT + index as u32,
+                    control as *mut f32 as *mut c_void,
+                );
+            }
+            let ports: [(u32, *mut c_void); 4] = [
+                (AUDIO_OUT_PORTS[0], lv2.left.as_mut_ptr() as *mut c_void),
+                (AUDIO_OUT_PORTS[1], lv2.right.as_mut_ptr() as *mut c_void),
+                (MIDI_IN_PORT, lv2.midi_in.as_mut_ptr() as *mut c_void),
+                (MIDI_OUT_PORT, lv2.midi_out.as_mut_ptr() as *mut c_void),
+            ];
+            for (port, data) in ports {
+                (descriptor.connect_port)(instance, port, data);
+            }
+            write_midi(&mut lv2.midi_in, lv2.midi_event, &[]);
+            lv2
+        }
+
+        fn gliss(&self) -> &Lv2Gliss {
+            unsafe { &*(self.instance as *const Lv2Gliss) }
+        }
+    }
+
+    impl Drop for Lv2 {
+        fn drop(&mut self) {
+            unsafe { ((*lv2_descriptor(0)).cleanup)(self.instance) }
+        }
+    }
+
+    impl PluginFormat for Lv2 {
+        fn state(&self) -> &EditorState {
+            &self.gliss().state
+        }
+
+        // the host moves the control port
+        fn set_param(&mut self, param: GlissParam, plain: f64) {
+            let index = GLISS_PARAMETERS.iter().position(|p| *p == param).unwrap();
+            self.controls[index] = plain as f32;
+        }
+
+        fn send_notes(&mut self, midi_numbers: &[u8]) {
+            let mut events = [[0u8; 3]; 16];
+            for (event, midi_number) in events.iter_mut().zip(midi_numbers) {
+                *event = [144, *midi_number, 100];
+            }
+            unsafe {
+                write_midi(
+                    &mut self.midi_in,
+                    self.midi_event,
+                    &events[..midi_numbers.len()],
+                )
+            };
+        }
+
+        fn process(&mut self, now: f64) -> usize {
+            let out_sequence = self.midi_out.as_mut_ptr() as *mut LV2_Atom_Sequence;
+            unsafe {
+                (*out_sequence).atom.size = (self.midi_out.len() * 8 - 8) as u32;
+                run_at(
+                    &mut *(self.instance as *mut Lv2Gliss),
+                    BLOCK_SIZE as u32,
+                    now,
+                );
+                write_midi(&mut self.midi_in, self.midi_event, &[]);
+                let event_size = pad_8(std::mem::size_of::<LV2_Atom_Event>() as u32 + 3);
+                ((*out_sequence).atom.size - 8) as usize / event_size as usize
+            }
+        }
+
+        fn output(&self) -> &[f32] {
+            &self.left
+        }
+    }
+
+    #[test]
+    fn bending_runs_without_allocating() {
+        let mut lv2 = unsafe { Lv2::new() };
+        bends_without_allocating(&mut lv2);
+        assert_eq!(lv2.left, lv2.right);
+    }
+}
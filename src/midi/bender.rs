@@ -7,13 +7,17 @@ use ordered_float::OrderedFloat;
 
 use vst::event::MidiEvent;
 
-use egui::{Pos2, Rect, Shape, Stroke, Ui};
+use egui::{Pos2, Shape, Stroke, Ui};
 
 use crate::draw::theme::GLISS_THEME;
 use crate::midi::paths::{BendPath, Path};
 use crate::midi::{Bend, Note};
 use crate::GLISS_EPOCH;
 
+// seconds of drawn benders kept, enough for the timeline zoomed out and scrolled all the way back
+pub const RENDER_HISTORY_SECONDS: f32 = 120.0;
+
+// drawn benders keyed by when they end, so expired ones split off the front
 pub struct RenderedBenders {
     map: BTreeMap<OrderedFloat<f32>, Vec<RenderedBender>>,
 }
@@ -31,67 +35,53 @@ impl RenderedBenders {
         }
     }
 
+    pub fn len(&self) -> usize {
+        self.map.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    // a new chord's benders cut off whatever the previous chords still had to draw
     pub fn append(&mut self, rbs: Vec<RenderedBender>) {
+        if let Some(cutoff) = rbs.iter().map(|rb| rb.start_time).reduce(f32::min) {
+            let superseded = self.map.split_off(&OrderedFloat(cutoff));
+            for mut rb in superseded.into_values().flatten() {
+                if rb.start_time < cutoff {
+                    rb.truncate(cutoff);
+                }
+                self.insert(rb);
+            }
+            self.retain(cutoff - RENDER_HISTORY_SECONDS);
+        }
         for rb in rbs.into_iter() {
             self.insert(rb);
         }
     }
 
     fn insert(&mut self, new_rb: RenderedBender) {
-        let start_time = OrderedFloat(new_rb.start_time);
-        match self.map.get_mut(&start_time) {
-            Some(vec) => vec.push(new_rb),
-            None => {
-                self.map.insert(start_time, vec![new_rb]);
-            }
-        }
+        self.map
+            .entry(OrderedFloat(new_rb.end_time))
+            .or_default()
+            .push(new_rb);
     }
 
+    // drops benders that ended before start_time
     pub fn retain(&mut self, start_time: f32) {
-        // TODO
-        // should use end_time not key
-        // refactor to BTreeMap<OrderedFloat<f32>, (end_time: Duration, Vec<RenderedBender>)> ?
-        // its not actually doing anything right now
-        let max_ui_window_duration = 5.0;
-        //        log::info!("retain call - start_time: {start_time}");
-        //        if let Some(example) = self.map.first_key_value() {
-        //            log::info!("retain call - example_key: {example:?}");
-        //        }
-        //        log::info!("retain call - start_time: {start_time}");
-        let start_time = OrderedFloat(start_time + max_ui_window_duration);
-        //log::info!("retain call - start_time2: {start_time}");
-        //let len_before = self.map.len();
-        self.map.retain(|&key, _| key <= start_time);
-        //        let len_after = self.map.len();
-        //        if len_before != len_after {
-        //            log::info!("retain call did something: {len_before} {len_after}");
-        //        }
+        self.map = self.map.split_off(&OrderedFloat(start_time));
     }
 
-    pub fn render(&mut self, ui: &Ui, to_screen: emath::RectTransform) {
-        let mut pairs = self.map.iter_mut().peekable();
-        while let Some((_, render_benders)) = pairs.next() {
-            let shapes: Vec<Shape> = render_benders
-                .iter()
-                .flat_map(|rb| rb.render(to_screen))
-                .collect();
-            if let Some((next_start_time, _)) = pairs.peek() {
-                // TODO
-                // dont under stand the double clone incured by peek
-                // refactor?
-                // https://stackoverflow.com/questions/62186871/how-to-correctly-use-peek-in-rust
-                //let cutoff_time = next_start_time.clone().clone().into();
-                //let cutoff_time = <&ordered_float::OrderedFloat<f32>>::clone(next_start_time).clone().into();
-                let cutoff_time =
-                    (*<&ordered_float::OrderedFloat<f32>>::clone(next_start_time)).into();
-                let cutoff = (to_screen * Pos2::new(cutoff_time, 0.0)).x;
-                ui.painter()
-                    .sub_region(Rect::everything_left_of(cutoff))
-                    .extend(shapes);
-            } else {
-                ui.painter().extend(shapes);
-            }
-        }
+    // only the benders overlapping start_time..end_time
+    pub fn render(&self, ui: &Ui, to_screen: emath::RectTransform, start_time: f32, end_time: f32) {
+        let shapes: Vec<Shape> = self
+            .map
+            .range(OrderedFloat(start_time)..)
+            .flat_map(|(_, rbs)| rbs.iter())
+            .filter(|rb| rb.start_time <= end_time)
+            .flat_map(|rb| rb.render(to_screen))
+            .collect();
+        ui.painter().extend(shapes);
     }
 }
 
@@ -140,9 +130,68 @@ impl RenderedBender {
         [bend_shape, hold_shape]
     }
 
-    // may not be nessisary, look for masking/cutoff egui utils
-    pub fn truncate(&mut self, _next_bender: RenderedBender) {
-        todo!()
+    // ends the bender at cutoff, where the next chord takes over its channel
+    pub fn truncate(&mut self, cutoff: f32) {
+        if cutoff >= self.end_time {
+            return;
+        }
+        let cutoff = cutoff.max(self.start_time);
+        if cutoff < self.hold.0.x {
+            let kept = self.bend.iter().take_while(|p| p.x < cutoff).count().max(1);
+            let (before, after) = (self.bend[kept - 1], self.bend[kept]);
+            let t = (cutoff - before.x) / (after.x - before.x);
+            let cut = Pos2::new(cutoff, before.y + t * (after.y - before.y));
+            self.bend.truncate(kept);
+            self.bend.push(cut);
+            self.hold = (cut, cut);
+        } else {
+            self.hold.1.x = cutoff;
+        }
+        self.end_time = cutoff;
+    }
+}
+
+// a pitch the bender passes through, held from arrive until depart
+#[derive(Debug, Clone, Copy)]
+pub struct Waypoint {
+    pub bend: Bend,
+    pub arrive: f64,
+    pub depart: f64,
+}
+
+// ramps a controller down while a surplus voice holds, the level sent last is kept
+#[derive(Debug, Clone, Copy)]
+pub struct Fade {
+    pub controller: u8,
+    // where the ramp starts, and the level a restore sends
+    pub nominal: u8,
+    pub level: u8,
+}
+
+impl Fade {
+    pub fn new(controller: u8, nominal: u8) -> Self {
+        Fade {
+            controller,
+            nominal,
+            level: nominal,
+        }
+    }
+
+    pub fn faded(&self) -> bool {
+        self.level < self.nominal
+    }
+
+    pub fn as_midi_event(&self, channel: u8) -> MidiEvent {
+        MidiEvent {
+            // control change
+            data: [175 + channel, self.controller, self.level],
+            delta_frames: 0,
+            live: false,
+            note_length: None,
+            note_offset: None,
+            detune: 0,
+            note_off_velocity: 0,
+        }
     }
 }
 
@@ -158,6 +207,9 @@ pub struct Bender {
     pub current_bend: Bend,
     pub bend_path: BendPath,
     pub pitch_bend_range: f32,
+    pub waypoint: Option<Waypoint>,
+    // surplus voices fade out between stop_time and note_off_time
+    pub fade: Option<Fade>,
 }
 
 impl Bender {
@@ -183,6 +235,14 @@ impl Bender {
         (bender, note.as_midi_event())
     }
 
+    // start away from the note, returning the bend to send before the note on
+    pub fn emerge_from(&mut self, waypoint: Waypoint) -> MidiEvent {
+        self.start_bend = waypoint.bend;
+        self.current_bend = waypoint.bend;
+        self.waypoint = Some(waypoint);
+        waypoint.bend.as_midi_event(self.note.channel)
+    }
+
     pub fn get_render(&self) -> RenderedBender {
         let og_note = self.note.midi_number;
         let bend_start = self
@@ -203,13 +263,26 @@ impl Bender {
                 let p1 = Pos2::new(start_time.as_secs_f32(), continuous_note1);
                 let p2 = Pos2::new(stop_time.as_secs_f32(), continuous_note2);
 
-                vec![p1, p2]
+                match self.waypoint {
+                    Some(waypoint) => {
+                        let continuous_waypoint = og_note as f32
+                            + waypoint.bend.continuous_semitones(self.pitch_bend_range);
+                        let [arrive, depart] = [waypoint.arrive, waypoint.depart].map(|t| {
+                            Pos2::new(
+                                (Duration::from_nanos(t as u64) - *GLISS_EPOCH).as_secs_f32(),
+                                continuous_waypoint,
+                            )
+                        });
+                        vec![p1, arrive, depart, p2]
+                    }
+                    None => vec![p1, p2],
+                }
             }
             _ => {
                 let p1 = Pos2::new(start_time.as_secs_f32(), continuous_note1);
                 let p2 = Pos2::new(stop_time.as_secs_f32(), continuous_note2);
 
-                log::info!("old method_endpoints: {:?}", vec![p1, p2]);
+                log::debug!("old method_endpoints: {:?}", vec![p1, p2]);
 
                 //let n_points = 50;
                 let n_points = 500;
@@ -248,7 +321,7 @@ impl Bender {
         let hold = (p1, p2);
 
         // TODO error check on index here or assert > 15 channles doesnt get this far?
-        log::info!("self.note.channel: {}", self.note.channel);
+        log::debug!("self.note.channel: {}", self.note.channel);
         let color = GLISS_THEME
             .channel_colors
             .get(self.note.channel as usize - 2);
@@ -262,21 +335,23 @@ impl Bender {
     pub fn update_target(
         &mut self,
         target: &Note,
+        waypoint: Option<Waypoint>,
         now: f64,
         bend_duration: f64,
         hold_duration: f64,
         bend_path: BendPath,
-    ) -> Result<RenderedBender, String> {
+    ) -> Result<(), String> {
         //log::info!("update_target called with target: {target:?}");
         //log::info!("pre update_target: {self:?}");
         self.target_bend = self.note.bend_to(target, self.pitch_bend_range)?;
+        self.waypoint = waypoint;
         self.start_bend = self.current_bend;
         self.start_time = now;
         self.stop_time = now + bend_duration;
         self.note_off_time = now + bend_duration + hold_duration;
         self.bend_path = bend_path;
-        log::info!("post update_target: {self:?}");
-        Ok(self.get_render())
+        log::debug!("post update_target: {self:?}");
+        Ok(())
     }
 
     pub fn get_bend(&self, time: f64) -> Option<Bend> {
@@ -284,14 +359,33 @@ impl Bender {
         let target_bend = self.target_bend.0 as f64;
         if self.start_time <= time && time <= self.stop_time {
             log::debug!("calling get_bend with: {:?}", self.bend_path);
-            let bend = BendPath::bend(
-                &self.bend_path,
-                time,
-                self.start_time,
-                self.stop_time,
-                start_bend,
-                target_bend,
-            );
+            let bend = match self.waypoint {
+                Some(waypoint) if time < waypoint.arrive => BendPath::bend(
+                    &self.bend_path,
+                    time,
+                    self.start_time,
+                    waypoint.arrive,
+                    start_bend,
+                    waypoint.bend.0 as f64,
+                ),
+                Some(waypoint) if time < waypoint.depart => waypoint.bend,
+                Some(waypoint) => BendPath::bend(
+                    &self.bend_path,
+                    time,
+                    waypoint.depart,
+                    self.stop_time,
+                    waypoint.bend.0 as f64,
+                    target_bend,
+                ),
+                None => BendPath::bend(
+                    &self.bend_path,
+                    time,
+                    self.start_time,
+                    self.stop_time,
+                    start_bend,
+                    target_bend,
+                ),
+            };
             Some(bend)
         } else {
             None
@@ -306,7 +400,7 @@ impl Bender {
             self.current_bend = bend;
             Some(bend.as_midi_event(self.note.channel))
         } else if self.note_off_time <= time {
-            log::info!("sending note off for: {}", self.note.midi_number);
+            log::debug!("sending note off for: {}", self.note.midi_number);
             self.active = false;
             Some(MidiEvent {
                 // note off
@@ -318,6 +412,16 @@ impl Bender {
                 detune: 0,
                 note_off_velocity: 0,
             })
+        } else if let Some(fade) = self.fade.as_mut() {
+            let t = (time - self.stop_time) / (self.note_off_time - self.stop_time);
+            let nominal = fade.nominal as f64;
+            let level = (nominal * (1.0 - t)).clamp(0.0, nominal) as u8;
+            if level < fade.level {
+                fade.level = level;
+                Some(fade.as_midi_event(self.note.channel))
+            } else {
+                None
+            }
         } else {
             None
         }
@@ -364,3 +468,56 @@ impl PartialEq for Bender {
 }
 
 impl Eq for Bender {}
+
+#[cfg(test)]
+mod render_history {
+    use super::{RenderedBender, RenderedBenders, RENDER_HISTORY_SECONDS};
+    use egui::{Pos2, Stroke};
+
+    fn rendered_bender(start_time: f32) -> RenderedBender {
+        let bend = vec![
+            Pos2::new(start_time, 60.0),
+            Pos2::new(start_time + 1.0, 62.0),
+            Pos2::new(start_time + 2.0, 64.0),
+        ];
+        let hold = (bend[2], Pos2::new(start_time + 4.0, 64.0));
+        RenderedBender::new(bend, hold, Stroke::default())
+    }
+
+    #[test]
+    fn truncate_cuts_the_bend_or_the_hold() {
+        let mut rb = rendered_bender(10.0);
+        rb.truncate(13.0);
+        assert_eq!(rb.end_time, 13.0);
+        assert_eq!(rb.hold.1, Pos2::new(13.0, 64.0));
+        assert_eq!(rb.bend.len(), 3);
+
+        rb.truncate(10.5);
+        assert_eq!(rb.end_time, 10.5);
+        assert_eq!(rb.bend, vec![Pos2::new(10.0, 60.0), Pos2::new(10.5, 61.0)]);
+        assert_eq!(rb.hold, (Pos2::new(10.5, 61.0), Pos2::new(10.5, 61.0)));
+
+        rb.truncate(20.0);
+        assert_eq!(rb.end_time, 10.5);
+    }
+
+    #[test]
+    fn history_stays_bounded() {
+        let mut rendered_benders = RenderedBenders::new();
+        // a four voice chord every second for an hour
+        for second in 0..3600 {
+            let rbs = (0..4).map(|_| rendered_bender(second as f32)).collect();
+            rendered_benders.append(rbs);
+        }
+        let expected = 4 * (RENDER_HISTORY_SECONDS as usize + 2);
+        assert!(
+            rendered_benders.len() <= expected,
+            "{:?}",
+            rendered_benders.len()
+        );
+        // everything but the newest chord was cut off by the chord after it
+        for rb in rendered_benders.map.values().flatten() {
+            assert!(rb.end_time <= 3599.0 || rb.start_time == 3599.0);
+        }
+    }
+}
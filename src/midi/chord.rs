@@ -1,13 +1,31 @@
 use std::time::{Duration, Instant};
 
+use rand::Rng;
+use smallvec::{smallvec, SmallVec};
 use vst::event::MidiEvent;
 
-use crate::midi::bender::{Bender, RenderedBender};
-use crate::midi::mapper::ChordMapper;
+use crate::midi::bender::{Bender, Fade, Waypoint, RENDER_HISTORY_SECONDS};
+use crate::midi::mapper::{ChordMapper, Unison};
 use crate::midi::paths::{BendPath, BendPathBuilder};
-use crate::midi::Note;
+use crate::midi::rng::with_rng;
+use crate::midi::voicing::Voicer;
+use crate::midi::{ChannelVec, Note};
+use crate::state::GlissParam;
 use crate::GLISS_EPOCH;
 
+// voices go out on midi channels 2 through 16
+pub const MAX_CHANNELS: usize = 15;
+
+// the timeline draws chords as long as their rendered benders, the newest chord is always kept
+pub fn expire_chords(chords: &mut Vec<Chord>, time: f64) {
+    let expired = time - RENDER_HISTORY_SECONDS as f64 * 1_000_000_000.0;
+    let n_expired = chords[..chords.len().saturating_sub(1)]
+        .iter()
+        .take_while(|chord| chord.start_time < expired)
+        .count();
+    chords.drain(..n_expired);
+}
+
 pub enum ChordAppendError {
     Early,
     Late,
@@ -19,7 +37,9 @@ pub enum ChordAppendError {
 // notes are sorted by midi number
 #[derive(Debug, Clone)]
 pub struct Chord {
-    pub notes: Vec<Note>,
+    pub notes: ChannelVec<Note>,
+    // what the benders play, filled in once the chord is sent to the bender
+    pub voices: ChannelVec<Note>,
     pub start_time: f64,
     pub capture_duration: f64,
     pub sent_to_bender: bool,
@@ -28,7 +48,8 @@ pub struct Chord {
 impl Chord {
     pub fn new(note: Note, capture_duration: f64) -> Self {
         Self {
-            notes: vec![note],
+            notes: smallvec![note],
+            voices: ChannelVec::new(),
             start_time: note.daw_time,
             capture_duration,
             sent_to_bender: false,
@@ -44,7 +65,8 @@ impl Chord {
 
         if let Some(start_time) = earliest_time {
             Ok(Self {
-                notes,
+                notes: notes.into_iter().collect(),
+                voices: ChannelVec::new(),
                 start_time,
                 capture_duration,
                 sent_to_bender: false,
@@ -65,7 +87,7 @@ impl Chord {
         if self.start_time + self.capture_duration < note.daw_time {
             return Err(ChordAppendError::Late);
         }
-        if self.notes.len() >= 15 {
+        if self.notes.len() >= MAX_CHANNELS {
             return Err(ChordAppendError::Full);
         }
         match self
@@ -81,10 +103,56 @@ impl Chord {
     }
 }
 
+// what happens to voices doubling a note when the next chord is smaller
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum SurplusVoices {
+    #[default]
+    Keep,
+    // note off as soon as the doubled voice arrives
+    Release,
+    // fade out with fade_controller over fade_duration after arriving
+    Fade,
+}
+
+impl SurplusVoices {
+    pub fn from_f32(val: f32) -> Self {
+        match (val * 3.0) as u32 {
+            0 => SurplusVoices::Keep,
+            1 => SurplusVoices::Release,
+            _ => SurplusVoices::Fade,
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            SurplusVoices::Keep => 0.0,
+            SurplusVoices::Release => 1.0 / 3.0,
+            SurplusVoices::Fade => 2.0 / 3.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod surplus_voices_categorical_param {
+    use super::SurplusVoices;
+
+    #[test]
+    fn there_and_back_again() {
+        for og_val in [0.0, 0.1, 1.0 / 3.0, 0.5, 2.0 / 3.0, 1.0].iter() {
+            let cat = SurplusVoices::from_f32(*og_val);
+            let as_cat_again = SurplusVoices::from_f32(cat.as_f64() as f32);
+            assert_eq!(cat, as_cat_again);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ChordBender {
     pub init_time: Instant,
     pub bend_duration: f64,
+    pub bend_duration_randomness: f64,
+    // semitones per nanosecond, where 0.0 means every voice uses bend_duration
+    pub bend_rate: f64,
     pub hold_duration: f64,
     pub pitch_bend_range: f32,
     pub chord_capture_duration: f64,
@@ -92,6 +160,19 @@ pub struct ChordBender {
     pub channels: Vec<Bender>,
     pub bend_path: BendPathBuilder,
     pub chord_mapper: ChordMapper,
+    pub voicer: Voicer,
+    pub surplus_voices: SurplusVoices,
+    // 7 for volume or 11 for expression
+    pub fade_controller: u8,
+    pub fade_duration: f64,
+    // the level fades start from and faded channels are brought back to
+    pub fade_level: u8,
+    // (channel, controller) left at a faded level after their note off
+    pub faded_channels: Vec<(u8, u8)>,
+    // new voices glide out from the nearest existing voice
+    pub split_new_voices: bool,
+    // off while nothing would read the copies of retargeted benders
+    pub copy_retargeted: bool,
 }
 
 impl ChordBender {
@@ -107,22 +188,31 @@ impl ChordBender {
         Self {
             init_time,
             bend_duration,
+            bend_duration_randomness: 0.0,
+            bend_rate: 0.0,
             hold_duration,
             pitch_bend_range,
             chord_capture_duration,
-            // We only ever need two chords?
-            // so use a different struct?
-            // array or ringbuffer sort of thing?
-            chords: vec![],
-            channels: vec![],
+            // only the newest chord, the ui keeps the history
+            chords: Vec::with_capacity(1),
+            channels: Vec::with_capacity(MAX_CHANNELS),
             bend_path: BendPathBuilder::default(),
             chord_mapper: ChordMapper::default(),
+            voicer: Voicer::default(),
+            surplus_voices: SurplusVoices::default(),
+            fade_controller: 7,
+            fade_duration: hold_duration,
+            fade_level: 100,
+            faded_channels: Vec::with_capacity(MAX_CHANNELS),
+            split_new_voices: false,
+            copy_retargeted: true,
         }
     }
 
     // TODO
     // not sure why we cant call &self here so that bend_duraion and hold_duration are implicit
     //pub fn new_channel(&self, channels: &mut Vec<Bender>, note: &mut Note, now: f64) -> Option<MidiEvent> {
+    #[allow(clippy::too_many_arguments)]
     fn new_channel(
         channels: &mut Vec<Bender>,
         note: &mut Note,
@@ -131,7 +221,8 @@ impl ChordBender {
         hold_duration: f64,
         pitch_bend_range: f32,
         bend_path: BendPath,
-    ) -> Option<(MidiEvent, RenderedBender)> {
+        emerge_from: Option<(f32, f64)>,
+    ) -> Option<SmallVec<[MidiEvent; 3]>> {
         let channel: u8 = match channels.iter().map(|bender| bender.note.channel).max() {
             Some(max_channel) if (2..=16).contains(&max_channel) => max_channel + 1,
             None => 2,
@@ -143,8 +234,8 @@ impl ChordBender {
         };
         note.channel = channel;
         note.new_note_on = true;
-        log::info!("new_channel called with bend_path: {bend_path:?}");
-        let (bender, new_note_event) = Bender::new(
+        log::debug!("new_channel called with bend_path: {bend_path:?}");
+        let (mut bender, new_note_event) = Bender::new(
             note,
             now,
             bend_duration,
@@ -153,19 +244,69 @@ impl ChordBender {
             bend_path,
         );
         //Bender::new(note, now, bend_duration, hold_duration, new_path);
-        let renderable = bender.get_render();
+        let mut midi_events = SmallVec::new();
+        // detuned voices sit off of their note for the whole bend
+        if note.detune != 0.0 {
+            match note.bend_to(note, pitch_bend_range) {
+                Ok(bend) => {
+                    bender.start_bend = bend;
+                    bender.target_bend = bend;
+                    bender.current_bend = bend;
+                    midi_events.push(bend.as_midi_event(channel));
+                }
+                Err(e) => log::warn!("new voice without detune: {e}"),
+            }
+        }
+        if let Some((midi, depart)) = emerge_from {
+            match note.bend_to_midi(midi, pitch_bend_range) {
+                Ok(bend) => {
+                    let waypoint = Waypoint {
+                        bend,
+                        arrive: now,
+                        depart,
+                    };
+                    midi_events.push(bender.emerge_from(waypoint));
+                    // drawn once the voice arrives on its own note
+                    note.new_note_on = false;
+                    note.bend_duration = bend_duration;
+                }
+                Err(e) => log::warn!("new voice starting on its own note: {e}"),
+            }
+        }
+        midi_events.push(new_note_event);
         channels.push(bender);
-        Some((new_note_event, renderable))
+        Some(midi_events)
+    }
+
+    // each voice either takes bend_duration or travels at bend_rate, +/- the randomness
+    pub fn voice_bend_duration(
+        n_semitones: f32,
+        bend_duration: f64,
+        bend_rate: f64,
+        bend_duration_randomness: f64,
+    ) -> f64 {
+        let duration = if bend_rate > 0.0 {
+            n_semitones.abs() as f64 / bend_rate
+        } else {
+            bend_duration
+        };
+        let randomness = if bend_duration_randomness > 0.0 {
+            with_rng(|rng| rng.gen_range(-bend_duration_randomness..=bend_duration_randomness))
+        } else {
+            0.0
+        };
+        let config = GlissParam::BendDuration.get_config();
+        (duration + randomness).max(config.min * config.ui_to_gliss_scalar)
     }
 
     fn sort_channels(&mut self) {
-        log::info!("channels before sort: {:?}", self.channels);
+        log::debug!("channels before sort: {:?}", self.channels);
         self.channels.sort_by(|a, b| {
             a.current_midi()
                 .partial_cmp(&b.current_midi())
                 .expect("no NANs")
         });
-        log::info!("channels after sort: {:?}", self.channels);
+        log::debug!("channels after sort: {:?}", self.channels);
     }
 
     pub fn push_event(&mut self, event: MidiEvent, host_time: f64) {
@@ -173,31 +314,32 @@ impl ChordBender {
             // midi note on
             144..=159 => {
                 if let Ok(note) = Note::new(event.data, host_time, self.bend_duration) {
-                    log::info!("push_event called with: {:?}", event.data);
+                    log::debug!("push_event called with: {:?}", event.data);
                     match self.chords.last_mut() {
                         None => {
-                            log::info!("in None branch");
+                            log::debug!("in None branch");
                             let chord = Chord::new(note, self.chord_capture_duration);
                             self.chords.push(chord);
                         }
                         Some(previous_chord) => {
-                            log::info!("in Some branch: {previous_chord:?}");
+                            log::debug!("in Some branch: {previous_chord:?}");
                             match previous_chord.append(note) {
                                 Ok(_) => (),
                                 Err(ChordAppendError::Late) => {
-                                    log::info!("attempted to append Late chord");
+                                    log::debug!("attempted to append Late chord");
                                     let chord = Chord::new(note, self.chord_capture_duration);
+                                    self.chords.clear();
                                     self.chords.push(chord);
                                 }
                                 Err(ChordAppendError::Early) => {
-                                    log::info!("attempted to append Early chord")
+                                    log::debug!("attempted to append Early chord")
                                 }
                                 Err(ChordAppendError::Exists) => {
-                                    log::info!("attempted to append existing note")
+                                    log::debug!("attempted to append existing note")
                                 }
                                 Err(ChordAppendError::Full) => {
                                     // TODO show this to user?
-                                    log::info!("attempted to append to a chord with 15 notes")
+                                    log::debug!("attempted to append to a chord with 15 notes")
                                 }
                             }
                         }
@@ -221,12 +363,12 @@ impl ChordBender {
         }
     }
 
-    // TODO return Renerers
+    // returns copies of the retargeted benders, the ui draws them
     fn update_target_chord(
         &mut self,
         now: f64,
-    ) -> Result<(Vec<MidiEvent>, Vec<RenderedBender>), String> {
-        //fn update_target_chord(&mut self, now: f64) -> Vec<MidiEvent> {
+        midi_events: &mut Vec<MidiEvent>,
+    ) -> Result<ChannelVec<Bender>, String> {
         self.sort_channels();
         let mut chord = self.chords.last_mut().expect("chords to be non-enpty");
         chord.sent_to_bender = true;
@@ -236,73 +378,216 @@ impl ChordBender {
             note.ui_time = note_on_time_dur;
         }
 
-        let mut midi_events = vec![];
-        let mut renderables = vec![];
-        log::info!("update_target_chord called with: {chord:?}");
+        let mut retargeted = ChannelVec::new();
+        log::debug!("update_target_chord called with: {chord:?}");
         let n_channels = self.channels.len();
         let n_notes = chord.notes.len();
-        log::info!("n_notes: {n_notes}, n_channels: {n_channels}");
+        log::debug!("n_notes: {n_notes}, n_channels: {n_channels}");
+        chord.voices = self.voicer.get_voices(&chord.notes);
 
-        log::info!("notes before mapper: {:?}", chord.notes);
+        log::debug!("voices before mapper: {:?}", chord.voices);
         let (target_note_indicies, new_note_indicies) =
-            self.chord_mapper.get_mapping(&self.channels, &chord.notes);
+            self.chord_mapper.get_mapping(&self.channels, &chord.voices);
+        let unison_midi = self
+            .chord_mapper
+            .get_unison_midi(&self.channels, &chord.voices);
+        let converging = unison_midi.is_some()
+            && self.chord_mapper.unison == Unison::Converge
+            && !self.channels.is_empty();
 
         // for testing how total randomness sounds
         //self.bend_path.path = None;
 
+        let (fixed_duration, bend_rate, randomness) = (
+            self.bend_duration,
+            self.bend_rate,
+            self.bend_duration_randomness,
+        );
+        // converging voices split bend_duration between meeting and spreading out
+        let share = if converging { 0.5 } else { 1.0 };
+        let segment_duration = |n_semitones: f32| {
+            ChordBender::voice_bend_duration(
+                n_semitones,
+                fixed_duration * share,
+                bend_rate,
+                randomness,
+            )
+        };
+        let converge_durations: ChannelVec<f64> = match unison_midi {
+            Some(unison_midi) if converging => self
+                .channels
+                .iter()
+                .map(|channel| segment_duration(unison_midi - channel.current_midi()))
+                .collect(),
+            _ => ChannelVec::new(),
+        };
+        // every voice waits on the unison until the slowest one arrives
+        let converged_time = now + converge_durations.iter().copied().fold(0.0, f64::max);
+
+        let existing_midis: ChannelVec<f32> = self
+            .channels
+            .iter()
+            .map(|channel| channel.current_midi())
+            .collect();
         let (bend_duration, hold_duration) = if target_note_indicies.is_empty() {
             (self.hold_duration, 0.0)
         } else {
             (self.bend_duration, self.hold_duration)
         };
         for new_note_idx in new_note_indicies {
-            if let Some((new_midi_event, renderable)) = ChordBender::new_channel(
+            let note = &mut chord.voices[new_note_idx];
+            let (bend_duration, hold_duration, bend_path, emerge_from) = match unison_midi {
+                Some(unison_midi) => (
+                    converged_time - now + segment_duration(note.midi() - unison_midi),
+                    self.hold_duration,
+                    self.bend_path.build(),
+                    Some((unison_midi, converged_time)),
+                ),
+                None if self.split_new_voices && !existing_midis.is_empty() => {
+                    let nearest_midi = existing_midis
+                        .iter()
+                        .copied()
+                        .min_by(|a, b| {
+                            (a - note.midi())
+                                .abs()
+                                .partial_cmp(&(b - note.midi()).abs())
+                                .expect("no NANs")
+                        })
+                        .expect("non-empty");
+                    (
+                        segment_duration(note.midi() - nearest_midi),
+                        self.hold_duration,
+                        self.bend_path.build(),
+                        Some((nearest_midi, now)),
+                    )
+                }
+                None => (bend_duration, hold_duration, BendPath::default(), None),
+            };
+            if let Some(new_midi_events) = ChordBender::new_channel(
                 &mut self.channels,
-                &mut chord.notes[new_note_idx],
+                note,
                 now,
                 bend_duration,
                 hold_duration,
                 self.pitch_bend_range as f32,
-                BendPath::default(),
+                bend_path,
+                emerge_from,
             ) {
+                let channel = self
+                    .channels
+                    .last()
+                    .expect("new channel pushed")
+                    .note
+                    .channel;
+                // a channel left silent by a fade gets its level back before the note on
+                if let Some(idx) = self
+                    .faded_channels
+                    .iter()
+                    .position(|(faded, _)| *faded == channel)
+                {
+                    let (_, controller) = self.faded_channels.remove(idx);
+                    midi_events.push(Fade::new(controller, self.fade_level).as_midi_event(channel));
+                }
                 //new_midi_events.push(new_midi_event);
-                midi_events.push(new_midi_event);
-                renderables.push(renderable);
+                midi_events.extend(new_midi_events);
+                if self.copy_retargeted {
+                    retargeted.push(self.channels.last().expect("new channel pushed").clone());
+                }
             }
             //midi_events.append(&mut new_midi_events);
         }
-        log::info!(
+        log::debug!(
             "chord_bender bend_path pre channel update: {:?}",
             self.bend_path
         );
+        // target notes are drawn once their slowest voice arrives
+        for &target_note_idx in target_note_indicies.iter() {
+            chord.voices[target_note_idx].bend_duration = 0.0;
+        }
+        // voices after the first heading to the same note are surplus
+        let mut targeted = [false; MAX_CHANNELS];
         //for (channel, note) in self.channels.iter_mut().zip(notes.into_iter()) {
-        for (channel, target_note_idx) in self.channels.iter_mut().zip(target_note_indicies) {
+        for (channel_idx, (channel, target_note_idx)) in self
+            .channels
+            .iter_mut()
+            .zip(target_note_indicies)
+            .enumerate()
+        {
             //        for (channel, note) in mapping {
-            let renderable = channel.update_target(
-                &chord.notes[target_note_idx],
+            let target_note = &mut chord.voices[target_note_idx];
+            let target_midi = target_note.midi();
+            let waypoint = match (unison_midi, converge_durations.get(channel_idx)) {
+                (Some(unison_midi), Some(converge_duration)) => {
+                    match channel
+                        .note
+                        .bend_to_midi(unison_midi, self.pitch_bend_range)
+                    {
+                        Ok(bend) => Some(Waypoint {
+                            bend,
+                            arrive: now + converge_duration,
+                            depart: converged_time,
+                        }),
+                        Err(e) => {
+                            log::warn!("voice skipping the unison: {e}");
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+            let bend_duration = match waypoint {
+                Some(waypoint) => {
+                    waypoint.depart - now
+                        + segment_duration(target_midi - unison_midi.expect("waypoint on unison"))
+                }
+                None => segment_duration(target_midi - channel.current_midi()),
+            };
+            target_note.bend_duration = target_note.bend_duration.max(bend_duration);
+            let surplus = std::mem::replace(&mut targeted[target_note_idx], true);
+            let (hold_duration, fade) = match self.surplus_voices {
+                SurplusVoices::Release if surplus => (0.0, None),
+                SurplusVoices::Fade if surplus => (
+                    self.fade_duration,
+                    Some(Fade::new(self.fade_controller, self.fade_level)),
+                ),
+                _ => (self.hold_duration, None),
+            };
+            // a voice caught mid fade is brought back up before it moves on
+            if let Some(faded) = channel.fade.filter(Fade::faded) {
+                midi_events.push(
+                    Fade::new(faded.controller, faded.nominal).as_midi_event(channel.note.channel),
+                );
+            }
+            channel.fade = fade;
+            channel.update_target(
+                target_note,
+                waypoint,
                 now,
-                self.bend_duration,
-                self.hold_duration,
+                bend_duration,
+                hold_duration,
                 self.bend_path.build(),
             )?;
-            renderables.push(renderable);
+            if self.copy_retargeted {
+                retargeted.push(channel.clone());
+            }
         }
 
-        log::info!("done update_target_chord:\n{:?}", self);
-        Ok((midi_events, renderables))
+        log::debug!("done update_target_chord:\n{:?}", self);
+        Ok(retargeted)
     }
 
-    // TODO return Renerers
-    //  -> (Vec<MidiEvent>, Vec<Renderable>) {
-    pub fn bend(&mut self, time: f64) -> Result<(Vec<MidiEvent>, Vec<RenderedBender>), String> {
-        let mut events = vec![];
-        let mut renderables = vec![];
+    // appends to events, which the caller keeps allocated between calls
+    // returns copies of the benders retargeted when a chord is sent
+    pub fn bend(
+        &mut self,
+        time: f64,
+        events: &mut Vec<MidiEvent>,
+    ) -> Result<ChannelVec<Bender>, String> {
+        let mut retargeted = ChannelVec::new();
 
         if let Some(chord) = self.chords.last() {
             if !chord.sent_to_bender && chord.done_capturing(time) {
-                let (mut new_events, mut new_renderables) = self.update_target_chord(time)?;
-                events.append(&mut new_events);
-                renderables.append(&mut new_renderables);
+                retargeted = self.update_target_chord(time, events)?;
             }
         }
 
@@ -313,8 +598,208 @@ impl ChordBender {
                 events.push(event);
             }
         }
+        for channel in self.channels.iter().filter(|bender| !bender.active) {
+            if let Some(fade) = channel.fade.filter(Fade::faded) {
+                self.faded_channels
+                    .push((channel.note.channel, fade.controller));
+            }
+        }
         //self.channels.retain(|&bender| bender.active);
         self.channels.retain(|bender| bender.active);
-        Ok((events, renderables))
+        Ok(retargeted)
+    }
+}
+
+#[cfg(test)]
+mod surplus_voices {
+    use super::{ChordBender, SurplusVoices};
+    use crate::midi::fixtures::{note_on, start_time, SECOND};
+    use std::time::Instant;
+    use vst::event::MidiEvent;
+
+    fn bend(chord_bender: &mut ChordBender, time: f64) -> Vec<MidiEvent> {
+        let mut events = vec![];
+        chord_bender.bend(time, &mut events).unwrap();
+        events
+    }
+
+    fn note_offs(events: &[MidiEvent]) -> usize {
+        events
+            .iter()
+            .filter(|event| (128..=143).contains(&event.data[0]))
+            .count()
+    }
+
+    // a triad then a single note, returning when the single note's bends stop
+    fn triad_to_one_note(chord_bender: &mut ChordBender) -> f64 {
+        let start = start_time();
+        for midi_number in [60, 64, 67] {
+            chord_bender.push_event(note_on(midi_number), start);
+        }
+        bend(chord_bender, start + 0.1 * SECOND);
+        assert_eq!(chord_bender.channels.len(), 3);
+        chord_bender.push_event(note_on(62), start + 2.0 * SECOND);
+        bend(chord_bender, start + 2.1 * SECOND);
+        start + 3.1 * SECOND
+    }
+
+    fn chord_bender(surplus_voices: SurplusVoices) -> ChordBender {
+        let mut chord_bender =
+            ChordBender::new(Instant::now(), SECOND, 10.0 * SECOND, 48.0, 0.01 * SECOND);
+        chord_bender.surplus_voices = surplus_voices;
+        chord_bender.fade_duration = SECOND;
+        chord_bender
+    }
+
+    #[test]
+    fn release_sends_note_off_to_the_doubled_voices() {
+        let mut chord_bender = chord_bender(SurplusVoices::Release);
+        let stop = triad_to_one_note(&mut chord_bender);
+        assert_eq!(note_offs(&bend(&mut chord_bender, stop + 0.01 * SECOND)), 2);
+        assert_eq!(chord_bender.channels.len(), 1);
+    }
+
+    #[test]
+    fn fade_ramps_the_doubled_voices_down_before_note_off() {
+        let mut chord_bender = chord_bender(SurplusVoices::Fade);
+        let stop = triad_to_one_note(&mut chord_bender);
+        let mut levels = vec![];
+        for fraction in [0.25, 0.5, 0.75, 0.999] {
+            let events = bend(&mut chord_bender, stop + fraction * SECOND);
+            assert_eq!(note_offs(&events), 0);
+            let fades: Vec<u8> = events
+                .iter()
+                .filter(|event| (176..=191).contains(&event.data[0]) && event.data[1] == 7)
+                .map(|event| event.data[2])
+                .collect();
+            assert_eq!(fades.len(), 2);
+            levels.push(fades[0]);
+        }
+        assert!(levels.windows(2).all(|pair| pair[1] < pair[0]));
+        assert_eq!(levels.last(), Some(&0));
+        assert_eq!(note_offs(&bend(&mut chord_bender, stop + 1.01 * SECOND)), 2);
+        assert_eq!(chord_bender.channels.len(), 1);
+    }
+
+    #[test]
+    fn faded_channels_come_back_at_the_fade_level() {
+        let mut chord_bender = chord_bender(SurplusVoices::Fade);
+        chord_bender.fade_level = 90;
+        let stop = triad_to_one_note(&mut chord_bender);
+        let fades = |events: &[MidiEvent]| -> Vec<u8> {
+            events
+                .iter()
+                .filter(|event| (176..=191).contains(&event.data[0]) && event.data[1] == 7)
+                .map(|event| event.data[2])
+                .collect()
+        };
+        // halfway down from the fade level
+        assert_eq!(
+            fades(&bend(&mut chord_bender, stop + 0.5 * SECOND)),
+            vec![45, 45]
+        );
+        assert_eq!(note_offs(&bend(&mut chord_bender, stop + 1.01 * SECOND)), 2);
+
+        // the next voices on those channels start at the fade level, not full volume
+        for midi_number in [60, 64, 67] {
+            chord_bender.push_event(note_on(midi_number), stop + 2.0 * SECOND);
+        }
+        let events = bend(&mut chord_bender, stop + 2.1 * SECOND);
+        assert_eq!(fades(&events), vec![90, 90]);
+    }
+}
+
+#[cfg(test)]
+mod split_new_voices {
+    use super::ChordBender;
+    use crate::midi::fixtures::{note_on, start_time, SECOND};
+    use std::time::Instant;
+
+    #[test]
+    fn new_voice_starts_on_the_nearest_voice() {
+        let mut chord_bender =
+            ChordBender::new(Instant::now(), SECOND, 10.0 * SECOND, 48.0, 0.01 * SECOND);
+        chord_bender.split_new_voices = true;
+        let start = start_time();
+        for midi_number in [60, 67] {
+            chord_bender.push_event(note_on(midi_number), start);
+        }
+        let mut events = vec![];
+        chord_bender
+            .bend(start + 0.1 * SECOND, &mut events)
+            .unwrap();
+        let existing: Vec<f32> = chord_bender
+            .channels
+            .iter()
+            .map(|channel| channel.current_midi())
+            .collect();
+        assert_eq!(existing, vec![60.0, 67.0]);
+
+        // none of the new notes are on an existing voice's pitch
+        for midi_number in [62, 65, 70] {
+            chord_bender.push_event(note_on(midi_number), start + 2.0 * SECOND);
+        }
+        events.clear();
+        chord_bender
+            .bend(start + 2.1 * SECOND, &mut events)
+            .unwrap();
+        let new_voice = chord_bender
+            .channels
+            .iter()
+            .find(|channel| channel.note.channel == 4)
+            .expect("a third channel");
+        let midi_number = new_voice.note.midi_number as f32;
+        let nearest = existing
+            .iter()
+            .copied()
+            .min_by(|a, b| {
+                (a - midi_number)
+                    .abs()
+                    .partial_cmp(&(b - midi_number).abs())
+                    .unwrap()
+            })
+            .unwrap();
+        let first_bend = events
+            .iter()
+            .find(|event| event.data[0] == 223 + 4)
+            .expect("a pitch bend on the new voice");
+        let u14 = ((first_bend.data[2] as u16) << 7) | first_bend.data[1] as u16;
+        let semitones = (u14 as f32 - 8192.0) / 8192.0 * 48.0;
+        assert!(semitones.abs() > 1.0);
+        assert!((midi_number + semitones - nearest).abs() < 0.01);
+        // the pitch bend goes out before the note on
+        let note_on_idx = events.iter().position(|event| event.data[0] == 143 + 4);
+        let bend_idx = events.iter().position(|event| event.data[0] == 223 + 4);
+        assert!(bend_idx < note_on_idx);
+    }
+}
+
+#[cfg(test)]
+mod voice_bend_duration {
+    use super::ChordBender;
+    use crate::midi::fixtures::SECOND;
+
+    #[test]
+    fn fixed_when_rate_is_zero() {
+        let short = ChordBender::voice_bend_duration(1.0, 2.0 * SECOND, 0.0, 0.0);
+        let long = ChordBender::voice_bend_duration(20.0, 2.0 * SECOND, 0.0, 0.0);
+        assert_eq!(short, long);
+    }
+
+    #[test]
+    fn proportional_to_interval() {
+        let rate = 4.0 / SECOND;
+        let up = ChordBender::voice_bend_duration(8.0, 2.0 * SECOND, rate, 0.0);
+        let down = ChordBender::voice_bend_duration(-2.0, 2.0 * SECOND, rate, 0.0);
+        assert!((up - 2.0 * SECOND).abs() < 1.0);
+        assert!((down - 0.5 * SECOND).abs() < 1.0);
+    }
+
+    #[test]
+    fn randomness_stays_in_range() {
+        for _ in 0..100 {
+            let duration = ChordBender::voice_bend_duration(0.0, 2.0 * SECOND, 0.0, SECOND);
+            assert!((SECOND..=3.0 * SECOND).contains(&duration));
+        }
     }
 }
@@ -1,9 +1,14 @@
 use crate::midi::bender::Bender;
-use crate::midi::Note;
+use crate::midi::chord::MAX_CHANNELS;
+use crate::midi::rng::with_rng;
+use crate::midi::{ChannelVec, Note};
 use std::cmp::Ordering::{Equal, Greater, Less};
-use std::collections::LinkedList;
 
 use rand::prelude::SliceRandom;
+use smallvec::{smallvec, SmallVec};
+
+// target note indicies for existing channels and target note indicies for new channels
+type Mapping = (ChannelVec<usize>, ChannelVec<usize>);
 
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub enum ChordMap {
@@ -11,14 +16,29 @@ pub enum ChordMap {
     Closest,
     Flipped,
     Random,
+    Optimal,
+    // voices meet on a single pitch between chords
+    Unison,
 }
 
 impl ChordMap {
+    pub const ALL: [ChordMap; 5] = [
+        ChordMap::Closest,
+        ChordMap::Flipped,
+        ChordMap::Random,
+        ChordMap::Optimal,
+        ChordMap::Unison,
+    ];
+
+    // the original three maps keep the thirds saved sessions and automation stored them in,
+    // later maps split the top third so old values still load as the map they were saved as
     pub fn from_f32(val: f32) -> Self {
-        match (val * 3.0) as u32 {
-            0 => ChordMap::Closest,
-            1 => ChordMap::Flipped,
-            _ => ChordMap::Random,
+        match (val * 9.0) as u32 {
+            0..=2 => ChordMap::Closest,
+            3..=5 => ChordMap::Flipped,
+            6 => ChordMap::Random,
+            7 => ChordMap::Optimal,
+            _ => ChordMap::Unison,
         }
     }
 
@@ -27,6 +47,8 @@ impl ChordMap {
             ChordMap::Closest => 0.0,
             ChordMap::Flipped => 1.0 / 3.0,
             ChordMap::Random => 2.0 / 3.0,
+            ChordMap::Optimal => 7.0 / 9.0,
+            ChordMap::Unison => 8.0 / 9.0,
         }
     }
 }
@@ -37,7 +59,7 @@ mod chord_map_categorical_param {
 
     #[test]
     fn there_and_back_again() {
-        for og_val in [0.0, 0.1, 1.0 / 3.0, 2.0 / 3.0, 1.0].iter() {
+        for og_val in [0.0, 0.1, 1.0 / 3.0, 2.0 / 3.0, 3.0 / 4.0, 4.0 / 5.0, 1.0].iter() {
             let cat = ChordMap::from_f32(*og_val);
             println!("cat: {cat:?}");
             let as_val = cat.as_f64();
@@ -47,10 +69,27 @@ mod chord_map_categorical_param {
             assert_eq!(cat, as_cat_again);
         }
     }
+
+    #[test]
+    fn values_saved_with_three_maps_still_load() {
+        // what the original Closest, Flipped and Random buttons stored
+        let saved = [
+            (0.0, ChordMap::Closest),
+            (1.0 / 3.0, ChordMap::Flipped),
+            (2.0 / 3.0, ChordMap::Random),
+        ];
+        for (value, chord_map) in saved.iter() {
+            assert_eq!(ChordMap::from_f32(*value as f32), *chord_map);
+        }
+        assert_eq!(ChordMap::from_f32(0.3), ChordMap::Closest);
+        assert_eq!(ChordMap::from_f32(0.6), ChordMap::Flipped);
+    }
 }
 
 // too combinitorial?
 // for MVP yes
+// decides which target notes existing voices move to when the chord grows
+// and which target notes get doubled when the chord shrinks
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub enum MappingPreference {
     #[default]
@@ -62,21 +101,153 @@ pub enum MappingPreference {
     Random,
 }
 
+impl MappingPreference {
+    pub const ALL: [MappingPreference; 6] = [
+        MappingPreference::Closest,
+        MappingPreference::Furthest,
+        MappingPreference::Top,
+        MappingPreference::Center,
+        MappingPreference::Bottom,
+        MappingPreference::Random,
+    ];
+
+    pub fn from_f32(val: f32) -> Self {
+        match (val * 6.0) as u32 {
+            0 => MappingPreference::Closest,
+            1 => MappingPreference::Furthest,
+            2 => MappingPreference::Top,
+            3 => MappingPreference::Center,
+            4 => MappingPreference::Bottom,
+            _ => MappingPreference::Random,
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            MappingPreference::Closest => 0.0,
+            MappingPreference::Furthest => 1.0 / 6.0,
+            MappingPreference::Top => 2.0 / 6.0,
+            MappingPreference::Center => 3.0 / 6.0,
+            MappingPreference::Bottom => 4.0 / 6.0,
+            MappingPreference::Random => 5.0 / 6.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod mapping_preference_categorical_param {
+    use super::MappingPreference;
+
+    #[test]
+    fn there_and_back_again() {
+        for og_val in [0.0, 0.1, 2.0 / 6.0, 0.5, 4.0 / 6.0, 5.0 / 6.0, 1.0].iter() {
+            let cat = MappingPreference::from_f32(*og_val);
+            let as_cat_again = MappingPreference::from_f32(cat.as_f64() as f32);
+            assert_eq!(cat, as_cat_again);
+        }
+    }
+}
+
+// what ChordMap::Optimal minimizes
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum VoiceLeadingCost {
+    // sum of semitones moved by every voice
+    #[default]
+    Total,
+    // semitones moved by the voice that moves furthest
+    Max,
+}
+
+impl VoiceLeadingCost {
+    pub fn from_f32(val: f32) -> Self {
+        match (val * 2.0) as u32 {
+            0 => VoiceLeadingCost::Total,
+            _ => VoiceLeadingCost::Max,
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            VoiceLeadingCost::Total => 0.0,
+            VoiceLeadingCost::Max => 1.0 / 2.0,
+        }
+    }
+
+    // (cost, total motion) so ties on Max prefer less overall motion
+    fn add(&self, motion: (f32, f32), distance: f32) -> (f32, f32) {
+        match self {
+            VoiceLeadingCost::Total => (motion.0 + distance, motion.1 + distance),
+            VoiceLeadingCost::Max => (motion.0.max(distance), motion.1 + distance),
+        }
+    }
+}
+
+// how ChordMap::Unison passes through its unison pitch
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum Unison {
+    // existing voices meet before spreading out to the new chord
+    #[default]
+    Converge,
+    // new voices start on the unison and spread out to their notes
+    Diverge,
+}
+
+impl Unison {
+    pub fn from_f32(val: f32) -> Self {
+        match (val * 2.0) as u32 {
+            0 => Unison::Converge,
+            _ => Unison::Diverge,
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Unison::Converge => 0.0,
+            Unison::Diverge => 1.0 / 2.0,
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy)]
 pub struct ChordMapper {
     pub chord_map: ChordMap,
     pub preference: MappingPreference,
+    pub cost: VoiceLeadingCost,
+    pub prevent_crossings: bool,
+    pub unison: Unison,
+    // None uses the centroid of the voices meeting on the unison
+    pub unison_midi: Option<f32>,
 }
 
 impl ChordMapper {
+    // the pitch ChordMap::Unison voices pass through, None for every other ChordMap
+    pub fn get_unison_midi(&self, channels: &[Bender], target_notes: &[Note]) -> Option<f32> {
+        if self.chord_map != ChordMap::Unison {
+            return None;
+        }
+        if let Some(midi) = self.unison_midi {
+            return Some(midi);
+        }
+        let midis: ChannelVec<f32> = match self.unison {
+            Unison::Converge if !channels.is_empty() => channels
+                .iter()
+                .map(|bender| bender.current_midi())
+                .collect(),
+            _ => target_notes
+                .iter()
+                .map(|note| note.midi_number as f32)
+                .collect(),
+        };
+        if midis.is_empty() {
+            return None;
+        }
+        Some(midis.iter().sum::<f32>() / midis.len() as f32)
+    }
+
     // TODO retrun indicies of bend_to_notes and new_notes
-    pub fn get_mapping(
-        self,
-        channels: &[Bender],
-        target_notes: &[Note],
-    ) -> (Vec<usize>, Vec<usize>) {
+    pub fn get_mapping(self, channels: &[Bender], target_notes: &[Note]) -> Mapping {
         if channels.is_empty() {
-            return (vec![], (0..target_notes.len()).collect());
+            return (ChannelVec::new(), (0..target_notes.len()).collect());
         }
         //        let &mut ordered_channels  = channels.clone();
         //        ordered_channels.sort_by(|a, b| {
@@ -84,97 +255,424 @@ impl ChordMapper {
         //        });
         // TODO handle this here?
         if target_notes.len() == 1 {
-            return ((0..channels.len()).map(|_| 0).collect(), vec![]);
+            return ((0..channels.len()).map(|_| 0).collect(), ChannelVec::new());
+        }
+        if self.chord_map == ChordMap::Optimal {
+            return get_optimal_mapping(channels, target_notes, self.cost, self.prevent_crossings);
+        }
+        // Closest keeps the note selection built into each ChordMap
+        if self.preference != MappingPreference::Closest {
+            return get_preferred_mapping(self.chord_map, self.preference, channels, target_notes);
         }
         match self.chord_map {
             ChordMap::Random => {
-                log::info!("in mapper branch random");
+                log::debug!("in mapper branch random");
                 get_random_mapping(channels, target_notes)
             }
             ChordMap::Flipped => get_flipped_mapping(channels, target_notes),
-            ChordMap::Closest => get_closest_mapping(channels, target_notes),
+            ChordMap::Closest | ChordMap::Unison => get_closest_mapping(channels, target_notes),
+            ChordMap::Optimal => unreachable!("handled above"),
+        }
+    }
+}
+
+// orders target note indicies from most to least preferred
+fn rank_target_notes(
+    preference: MappingPreference,
+    channels: &[Bender],
+    target_notes: &[Note],
+) -> ChannelVec<usize> {
+    let mut ranking: ChannelVec<usize> = (0..target_notes.len()).collect();
+    let distance_to_channels = |idx: &usize| {
+        let midi = target_notes[*idx].midi_number as f32;
+        channels
+            .iter()
+            .map(|bender| (bender.current_midi() - midi).abs())
+            .fold(f32::INFINITY, f32::min)
+    };
+    match preference {
+        MappingPreference::Closest => ranking.sort_by(|a, b| {
+            distance_to_channels(a)
+                .partial_cmp(&distance_to_channels(b))
+                .expect("no NANs")
+        }),
+        MappingPreference::Furthest => ranking.sort_by(|a, b| {
+            distance_to_channels(b)
+                .partial_cmp(&distance_to_channels(a))
+                .expect("no NANs")
+        }),
+        MappingPreference::Top => ranking.reverse(),
+        MappingPreference::Center => {
+            let center = (target_notes.len() - 1) as f32 / 2.0;
+            ranking.sort_by(|a, b| {
+                (*a as f32 - center)
+                    .abs()
+                    .partial_cmp(&(*b as f32 - center).abs())
+                    .expect("no NANs")
+            });
+        }
+        MappingPreference::Bottom => (),
+        MappingPreference::Random => with_rng(|rng| ranking.shuffle(rng)),
+    }
+    ranking
+}
+
+fn get_preferred_mapping(
+    chord_map: ChordMap,
+    preference: MappingPreference,
+    channels: &[Bender],
+    target_notes: &[Note],
+) -> Mapping {
+    let n_channels = channels.len();
+    let n_target_notes = target_notes.len();
+    let ranking = rank_target_notes(preference, channels, target_notes);
+    log::debug!("{preference:?} ranking: {ranking:?}");
+    let (mut target_note_indicies, new_note_indicies) = match n_channels.cmp(&n_target_notes) {
+        Less => (
+            ChannelVec::from_slice(&ranking[..n_channels]),
+            ChannelVec::from_slice(&ranking[n_channels..]),
+        ),
+        Equal => (ranking, ChannelVec::new()),
+        Greater => {
+            let doubled = ranking
+                .into_iter()
+                .cycle()
+                .take(n_channels - n_target_notes);
+            (
+                (0..n_target_notes).chain(doubled).collect(),
+                ChannelVec::new(),
+            )
+        }
+    };
+    // channels and target notes are both sorted by pitch
+    target_note_indicies.sort_unstable();
+    match chord_map {
+        ChordMap::Closest | ChordMap::Unison => (),
+        ChordMap::Flipped => target_note_indicies.reverse(),
+        ChordMap::Random => with_rng(|rng| target_note_indicies.shuffle(rng)),
+        ChordMap::Optimal => unreachable!("handled by get_optimal_mapping"),
+    }
+    log::debug!("target_note_indicies: {target_note_indicies:?}");
+    log::debug!("new_note_indicies: {new_note_indicies:?}");
+    (target_note_indicies, new_note_indicies)
+}
+
+// globally minimizes voice motion instead of greedily matching channels in order
+// every target note is reached when there are more channels than notes
+fn get_optimal_mapping(
+    channels: &[Bender],
+    target_notes: &[Note],
+    cost: VoiceLeadingCost,
+    prevent_crossings: bool,
+) -> Mapping {
+    let channel_midis: ChannelVec<f32> = channels
+        .iter()
+        .map(|bender| bender.current_midi())
+        .collect();
+    let target_midis: ChannelVec<f32> = target_notes
+        .iter()
+        .map(|note| note.midi_number as f32)
+        .collect();
+    let target_note_indicies = if prevent_crossings {
+        get_monotone_assignment(&channel_midis, &target_midis, cost)
+    } else {
+        get_unconstrained_assignment(&channel_midis, &target_midis, cost)
+    };
+    let new_note_indicies = (0..target_notes.len())
+        .filter(|idx| !target_note_indicies.contains(idx))
+        .collect();
+    log::debug!("optimal target_note_indicies: {target_note_indicies:?}");
+    log::debug!("optimal new_note_indicies: {new_note_indicies:?}");
+    (target_note_indicies, new_note_indicies)
+}
+
+// dynamic program over pitch sorted voices where the assigned targets never decrease
+fn get_monotone_assignment(
+    channel_midis: &[f32],
+    target_midis: &[f32],
+    cost: VoiceLeadingCost,
+) -> ChannelVec<usize> {
+    let n_channels = channel_midis.len();
+    let n_targets = target_midis.len();
+    let mut order: ChannelVec<usize> = (0..n_channels).collect();
+    order.sort_by(|a, b| {
+        channel_midis[*a]
+            .partial_cmp(&channel_midis[*b])
+            .expect("no NANs")
+    });
+    let distance = |i: usize, j: usize| (channel_midis[order[i]] - target_midis[j]).abs();
+    let better = |l: Option<(f32, f32)>, r: Option<(f32, f32)>| match (l, r) {
+        (Some(l), Some(r)) if r < l => Some(r),
+        (None, r) => r,
+        (l, _) => l,
+    };
+
+    // motion[i][j] is the best motion of the first i voices using the first j targets
+    // where voice i - 1 lands on target j - 1 when stepped is true
+    let mut motion = [[None; MAX_CHANNELS + 1]; MAX_CHANNELS + 1];
+    let mut stepped = [[false; MAX_CHANNELS + 1]; MAX_CHANNELS + 1];
+    if n_channels <= n_targets {
+        // each voice gets its own target, skipped targets become new notes
+        motion[0] = [Some((0.0, 0.0)); MAX_CHANNELS + 1];
+        for i in 1..=n_channels {
+            for j in i..=n_targets {
+                let skip = if j > i { motion[i][j - 1] } else { None };
+                let take = motion[i - 1][j - 1].map(|m| cost.add(m, distance(i - 1, j - 1)));
+                motion[i][j] = better(skip, take);
+                stepped[i][j] = motion[i][j] == take && take.is_some();
+            }
+        }
+    } else {
+        // voices are split into consecutive groups, one group per target
+        motion[0][0] = Some((0.0, 0.0));
+        for i in 1..=n_channels {
+            for j in 1..=n_targets.min(i) {
+                let same = motion[i - 1][j];
+                let next = motion[i - 1][j - 1];
+                let previous = better(same, next);
+                motion[i][j] = previous.map(|m| cost.add(m, distance(i - 1, j - 1)));
+                stepped[i][j] = previous == next && next.is_some();
+            }
+        }
+    }
+
+    let mut sorted_targets = [0; MAX_CHANNELS];
+    let (mut i, mut j) = (n_channels, n_targets);
+    while i > 0 {
+        if n_channels <= n_targets {
+            if stepped[i][j] {
+                sorted_targets[i - 1] = j - 1;
+                i -= 1;
+            }
+            j -= 1;
+        } else {
+            sorted_targets[i - 1] = j - 1;
+            if stepped[i][j] {
+                j -= 1;
+            }
+            i -= 1;
+        }
+    }
+
+    let mut target_note_indicies: ChannelVec<usize> = smallvec![0; n_channels];
+    for (sorted_idx, channel_idx) in order.into_iter().enumerate() {
+        target_note_indicies[channel_idx] = sorted_targets[sorted_idx];
+    }
+    target_note_indicies
+}
+
+// optimal assignment where voices may cross each other
+fn get_unconstrained_assignment(
+    channel_midis: &[f32],
+    target_midis: &[f32],
+    cost: VoiceLeadingCost,
+) -> ChannelVec<usize> {
+    let n_channels = channel_midis.len();
+    let n_targets = target_midis.len();
+    let size = n_channels.max(n_targets);
+    let nearest_target = |i: usize| {
+        (0..n_targets)
+            .map(|j| (j, (channel_midis[i] - target_midis[j]).abs()))
+            .reduce(|l, r| if r.1 < l.1 { r } else { l })
+            .expect("non-empty targets")
+    };
+    // rows past n_channels are placeholders for new notes
+    // columns past n_targets are surplus channels free to double their nearest target
+    let distance = |i: usize, j: usize| -> f64 {
+        if i >= n_channels {
+            0.0
+        } else if j >= n_targets {
+            nearest_target(i).1 as f64
+        } else {
+            (channel_midis[i] - target_midis[j]).abs() as f64
+        }
+    };
+    let mut distances = [[0.0; MAX_CHANNELS]; MAX_CHANNELS];
+    for (i, row) in distances.iter_mut().enumerate().take(size) {
+        for (j, d) in row.iter_mut().enumerate().take(size) {
+            *d = distance(i, j);
+        }
+    }
+
+    if cost == VoiceLeadingCost::Max {
+        // smallest threshold that still allows a complete assignment
+        let mut thresholds: SmallVec<[f64; MAX_CHANNELS * MAX_CHANNELS]> = distances[..size]
+            .iter()
+            .flat_map(|row| &row[..size])
+            .copied()
+            .collect();
+        thresholds.sort_by(|l, r| l.partial_cmp(r).expect("no NANs"));
+        thresholds.dedup();
+        let capped = |threshold: f64| -> CostMatrix {
+            distances.map(|row| row.map(|d| if d > threshold { UNREACHABLE + d } else { d }))
+        };
+        let feasible = |threshold: f64| {
+            let capped = capped(threshold);
+            min_cost_assignment(&capped, size)
+                .iter()
+                .enumerate()
+                .all(|(i, &j)| capped[i][j] < UNREACHABLE)
+        };
+        let idx = thresholds.partition_point(|&threshold| !feasible(threshold));
+        distances = capped(thresholds[idx.min(thresholds.len() - 1)]);
+    }
+
+    min_cost_assignment(&distances, size)
+        .into_iter()
+        .take(n_channels)
+        .enumerate()
+        .map(|(i, j)| {
+            if j >= n_targets {
+                nearest_target(i).0
+            } else {
+                j
+            }
+        })
+        .collect()
+}
+
+const UNREACHABLE: f64 = 1_000_000.0;
+
+// only the first size rows and columns are used
+type CostMatrix = [[f64; MAX_CHANNELS]; MAX_CHANNELS];
+
+// hungarian algorithm, returns the column assigned to each row of a square cost matrix
+fn min_cost_assignment(costs: &CostMatrix, size: usize) -> ChannelVec<usize> {
+    let n = size;
+    let mut row_potential = [0.0; MAX_CHANNELS + 1];
+    let mut col_potential = [0.0; MAX_CHANNELS + 1];
+    // 1-indexed row matched to each column, column 0 is the row being inserted
+    let mut col_match = [0; MAX_CHANNELS + 1];
+    let mut way = [0; MAX_CHANNELS + 1];
+    for row in 1..=n {
+        col_match[0] = row;
+        let mut col = 0;
+        let mut min_slack = [f64::INFINITY; MAX_CHANNELS + 1];
+        let mut used = [false; MAX_CHANNELS + 1];
+        loop {
+            used[col] = true;
+            let matched_row = col_match[col];
+            let mut delta = f64::INFINITY;
+            let mut next_col = 0;
+            for j in 1..=n {
+                if !used[j] {
+                    let slack = costs[matched_row - 1][j - 1]
+                        - row_potential[matched_row]
+                        - col_potential[j];
+                    if slack < min_slack[j] {
+                        min_slack[j] = slack;
+                        way[j] = col;
+                    }
+                    if min_slack[j] < delta {
+                        delta = min_slack[j];
+                        next_col = j;
+                    }
+                }
+            }
+            for j in 0..=n {
+                if used[j] {
+                    row_potential[col_match[j]] += delta;
+                    col_potential[j] -= delta;
+                } else {
+                    min_slack[j] -= delta;
+                }
+            }
+            col = next_col;
+            if col_match[col] == 0 {
+                break;
+            }
         }
+        while col != 0 {
+            let previous_col = way[col];
+            col_match[col] = col_match[previous_col];
+            col = previous_col;
+        }
+    }
+    let mut assignment: ChannelVec<usize> = smallvec![0; n];
+    for (col, &row) in col_match.iter().enumerate().take(n + 1).skip(1) {
+        assignment[row - 1] = col - 1;
     }
+    assignment
 }
 
-fn get_random_mapping(channels: &[Bender], target_notes: &[Note]) -> (Vec<usize>, Vec<usize>) {
+fn get_random_mapping(channels: &[Bender], target_notes: &[Note]) -> Mapping {
     let n_channels = channels.len();
     let n_target_notes = target_notes.len();
-    let mut target_indicies: Vec<usize> = (0..n_target_notes).collect();
-    let mut rng = rand::thread_rng();
-    target_indicies.shuffle(&mut rng);
+    let mut target_indicies: ChannelVec<usize> = (0..n_target_notes).collect();
+    with_rng(|rng| target_indicies.shuffle(rng));
     match n_channels.cmp(&n_target_notes) {
         Less => {
-            let target_notes = target_indicies[..n_channels].to_vec();
-            let new_notes = target_indicies[n_channels..].to_vec();
+            let target_notes = ChannelVec::from_slice(&target_indicies[..n_channels]);
+            let new_notes = ChannelVec::from_slice(&target_indicies[n_channels..]);
             (target_notes, new_notes)
         }
-        Equal => (target_indicies.to_vec(), vec![]),
+        Equal => (target_indicies, ChannelVec::new()),
         Greater => (
             target_indicies
                 .into_iter()
                 .cycle()
                 .take(n_channels)
                 .collect(),
-            vec![],
+            ChannelVec::new(),
         ),
     }
 }
 
-fn get_flipped_mapping(channels: &[Bender], target_notes: &[Note]) -> (Vec<usize>, Vec<usize>) {
+fn get_flipped_mapping(channels: &[Bender], target_notes: &[Note]) -> Mapping {
     let n_channels = channels.len();
     let n_target_notes = target_notes.len();
     match n_channels.cmp(&n_target_notes) {
         Less => {
-            let mut target_indicies: LinkedList<usize> = (0..n_target_notes).collect();
-            let mut top_target_notes = vec![];
-            let mut bot_target_notes = vec![];
+            // taken from both ends
+            let mut target_indicies = 0..n_target_notes;
+            let mut top_target_notes = ChannelVec::new();
+            let mut bot_target_notes: ChannelVec<usize> = ChannelVec::new();
             for i in 0..n_channels {
                 if i % 2 == 0 {
-                    top_target_notes.push(target_indicies.pop_back().expect("safe in Less branch"));
+                    top_target_notes
+                        .push(target_indicies.next_back().expect("safe in Less branch"));
                 } else {
-                    bot_target_notes
-                        .push(target_indicies.pop_front().expect("safe in Less branch"));
+                    bot_target_notes.push(target_indicies.next().expect("safe in Less branch"));
                 }
             }
             for note in bot_target_notes.into_iter().rev() {
                 top_target_notes.push(note);
             }
             //top_target_notes.append(&mut bot_target_notes);
-            let new_notes = target_indicies.into_iter().collect();
-            log::info!("target_notes: {top_target_notes:?}");
-            log::info!("new_notes: {new_notes:?}");
+            let new_notes: ChannelVec<usize> = target_indicies.collect();
+            log::debug!("target_notes: {top_target_notes:?}");
+            log::debug!("new_notes: {new_notes:?}");
             (top_target_notes, new_notes)
         }
-        Equal => ((0..n_target_notes).into_iter().rev().collect(), vec![]),
+        Equal => ((0..n_target_notes).rev().collect(), ChannelVec::new()),
         Greater => {
             let half: usize = n_target_notes / 2;
-            let top_targets: Vec<usize> = (0..half).collect();
-            let mid_targets: Vec<usize>;
-            let bot_targets: Vec<usize>;
+            let top_targets = 0..half;
+            let mid_targets: ChannelVec<usize>;
+            let bot_targets;
             if n_target_notes % 2 == 0 {
                 mid_targets = (half - 1..half + 1).collect();
-                bot_targets = (half..n_target_notes).collect();
+                bot_targets = half..n_target_notes;
             } else {
-                mid_targets = vec![half];
-                bot_targets = (half + 1..n_target_notes).collect();
+                mid_targets = smallvec![half];
+                bot_targets = half + 1..n_target_notes;
             }
-            log::info!("top_targets: {top_targets:?}");
-            log::info!("mid_targets: {mid_targets:?}");
-            log::info!("bot_targets: {bot_targets:?}");
+            log::debug!("top_targets: {top_targets:?}");
+            log::debug!("mid_targets: {mid_targets:?}");
+            log::debug!("bot_targets: {bot_targets:?}");
 
-            let mut top_channels = vec![];
-            let mut bot_channels = vec![];
+            let mut top_channels: ChannelVec<usize> = ChannelVec::new();
+            let mut bot_channels: ChannelVec<usize> = ChannelVec::new();
 
-            log::info!("n_channels: {n_channels}");
-            log::info!("n_target_notes: {n_target_notes}");
+            log::debug!("n_channels: {n_channels}");
+            log::debug!("n_target_notes: {n_target_notes}");
             let group_size: usize =
                 if (n_target_notes % 2 != 0) && (n_channels % n_target_notes != 0) {
                     n_channels / (n_target_notes - 1)
                 } else {
                     n_channels / n_target_notes
                 };
-            log::info!("group_size: {group_size}");
+            log::debug!("group_size: {group_size}");
 
             for target in top_targets {
                 for _ in 0..group_size {
@@ -186,48 +684,48 @@ fn get_flipped_mapping(channels: &[Bender], target_notes: &[Note]) -> (Vec<usize
                     top_channels.push(target);
                 }
             }
-            log::info!("top_channels: {top_channels:?}");
-            log::info!("bot_channels: {bot_channels:?}");
+            log::debug!("top_channels: {top_channels:?}");
+            log::debug!("bot_channels: {bot_channels:?}");
 
             let n_mid_channels = (n_channels - top_channels.len()) - bot_channels.len();
-            log::info!("n_mid_channels: {n_mid_channels}");
+            log::debug!("n_mid_channels: {n_mid_channels}");
 
-            let mut mid_channels: Vec<usize> = mid_targets
+            let mut mid_channels: ChannelVec<usize> = mid_targets
                 .into_iter()
                 .cycle()
                 .take(n_mid_channels)
                 .collect();
 
-            log::info!("mid_channels: {mid_channels:?}");
+            log::debug!("mid_channels: {mid_channels:?}");
 
             top_channels.append(&mut mid_channels);
             top_channels.append(&mut bot_channels);
-            log::info!("output_channels: {top_channels:?}");
-            (top_channels, vec![])
+            log::debug!("output_channels: {top_channels:?}");
+            (top_channels, ChannelVec::new())
         }
     }
 }
 
-fn get_closest_mapping(channels: &[Bender], target_notes: &[Note]) -> (Vec<usize>, Vec<usize>) {
-    log::info!("channels: {channels:?}");
-    log::info!("target_notes: {target_notes:?}");
+fn get_closest_mapping(channels: &[Bender], target_notes: &[Note]) -> Mapping {
+    log::debug!("channels: {channels:?}");
+    log::debug!("target_notes: {target_notes:?}");
     let mut n_channels = channels.len();
     let mut n_target_notes = target_notes.len();
     match n_channels.cmp(&n_target_notes) {
         Less => {
-            let mut target_note_indicies: Vec<usize> = vec![];
-            let channel_midis: Vec<f32> = channels
+            let mut target_note_indicies: ChannelVec<usize> = ChannelVec::new();
+            let channel_midis: ChannelVec<f32> = channels
                 .iter()
                 .map(|bender| bender.current_midi())
                 .collect();
-            let mut target_midis: Vec<(usize, f32)> = target_notes
+            let mut target_midis: ChannelVec<(usize, f32)> = target_notes
                 .iter()
                 .map(|note| note.midi_number as f32)
                 .enumerate()
                 .collect();
             for midi in channel_midis {
-                log::info!("target_midis before: {:?}", target_midis);
-                log::info!("looking for closes to: {}", midi);
+                log::debug!("target_midis before: {:?}", target_midis);
+                log::debug!("looking for closes to: {}", midi);
                 let (closest_index, _min_distance) = target_midis
                     .iter()
                     .map(|(idx, target_midi)| (idx, (midi - target_midi).abs()))
@@ -243,19 +741,20 @@ fn get_closest_mapping(channels: &[Bender], target_notes: &[Note]) -> (Vec<usize
                     })
                     .expect("minimum to exist");
                 let closest_index = *closest_index;
-                log::info!("closest_index: {closest_index}");
+                log::debug!("closest_index: {closest_index}");
                 target_note_indicies.push(closest_index);
                 target_midis.retain(|(i, _)| i != &closest_index);
-                log::info!("target_midis after: {:?}", target_midis);
+                log::debug!("target_midis after: {:?}", target_midis);
             }
-            let new_note_indicies = target_midis.into_iter().map(|(i, _)| i).collect();
-            log::info!("target_note_indicies: {:?}", target_note_indicies);
-            log::info!("new_note_indicies: {:?}", new_note_indicies);
+            let new_note_indicies: ChannelVec<usize> =
+                target_midis.into_iter().map(|(i, _)| i).collect();
+            log::debug!("target_note_indicies: {:?}", target_note_indicies);
+            log::debug!("new_note_indicies: {:?}", new_note_indicies);
             (target_note_indicies, new_note_indicies)
         }
-        Equal => ((0..n_target_notes).collect(), vec![]),
+        Equal => ((0..n_target_notes).collect(), ChannelVec::new()),
         Greater => {
-            let mut target_notes = vec![];
+            let mut target_notes = ChannelVec::new();
             for note_idx in 0..n_target_notes {
                 let group_size = n_channels / n_target_notes;
                 for _ in 0..group_size {
@@ -264,8 +763,197 @@ fn get_closest_mapping(channels: &[Bender], target_notes: &[Note]) -> (Vec<usize
                 n_channels -= group_size;
                 n_target_notes -= 1;
             }
-            log::info!("closest target_notes: {target_notes:?}");
-            (target_notes, vec![])
+            log::debug!("closest target_notes: {target_notes:?}");
+            (target_notes, ChannelVec::new())
         }
     }
 }
+
+#[cfg(test)]
+mod preferred_mapping {
+    use super::{ChordMap, ChordMapper, MappingPreference};
+    use crate::midi::bender::Bender;
+    use crate::midi::Note;
+    use smallvec::smallvec;
+
+    fn benders(midi_numbers: &[u8]) -> Vec<Bender> {
+        midi_numbers
+            .iter()
+            .map(|&midi_number| Bender {
+                note: Note {
+                    midi_number,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn notes(midi_numbers: &[u8]) -> Vec<Note> {
+        midi_numbers
+            .iter()
+            .map(|&midi_number| Note {
+                midi_number,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn mapper(preference: MappingPreference) -> ChordMapper {
+        ChordMapper {
+            chord_map: ChordMap::Closest,
+            preference,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn growing_chord_keeps_preferred_notes() {
+        let channels = benders(&[60, 64]);
+        let targets = notes(&[48, 55, 60, 67, 72]);
+        let top = mapper(MappingPreference::Top).get_mapping(&channels, &targets);
+        assert_eq!(top, (smallvec![3, 4], smallvec![2, 1, 0]));
+        let bottom = mapper(MappingPreference::Bottom).get_mapping(&channels, &targets);
+        assert_eq!(bottom, (smallvec![0, 1], smallvec![2, 3, 4]));
+        let center = mapper(MappingPreference::Center).get_mapping(&channels, &targets);
+        assert_eq!(center, (smallvec![1, 2], smallvec![3, 0, 4]));
+        let furthest = mapper(MappingPreference::Furthest).get_mapping(&channels, &targets);
+        assert_eq!(furthest, (smallvec![0, 4], smallvec![1, 3, 2]));
+    }
+
+    #[test]
+    fn shrinking_chord_doubles_preferred_notes() {
+        let channels = benders(&[48, 52, 55, 60, 64]);
+        let targets = notes(&[50, 57, 62]);
+        let top = mapper(MappingPreference::Top).get_mapping(&channels, &targets);
+        assert_eq!(top, (smallvec![0, 1, 1, 2, 2], smallvec![]));
+        let bottom = mapper(MappingPreference::Bottom).get_mapping(&channels, &targets);
+        assert_eq!(bottom, (smallvec![0, 0, 1, 1, 2], smallvec![]));
+    }
+}
+
+#[cfg(test)]
+mod optimal_mapping {
+    use super::{get_monotone_assignment, get_unconstrained_assignment, VoiceLeadingCost};
+
+    fn total_motion(channels: &[f32], targets: &[f32], mapping: &[usize]) -> f32 {
+        channels
+            .iter()
+            .zip(mapping)
+            .map(|(c, &t)| (c - targets[t]).abs())
+            .sum()
+    }
+
+    fn max_motion(channels: &[f32], targets: &[f32], mapping: &[usize]) -> f32 {
+        channels
+            .iter()
+            .zip(mapping)
+            .map(|(c, &t)| (c - targets[t]).abs())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn beats_greedy_on_total_motion() {
+        // greedy sends 60 -> 61 and leaves 61 -> 50
+        let channels = [60.0, 61.0];
+        let targets = [50.0, 61.0];
+        for mapping in [
+            get_monotone_assignment(&channels, &targets, VoiceLeadingCost::Total),
+            get_unconstrained_assignment(&channels, &targets, VoiceLeadingCost::Total),
+        ] {
+            assert_eq!(total_motion(&channels, &targets, &mapping), 10.0);
+        }
+    }
+
+    #[test]
+    fn monotone_never_crosses() {
+        let channels = [64.0, 48.0, 55.0, 60.0];
+        let targets = [50.0, 53.0, 57.0, 62.0, 65.0, 69.0];
+        let mapping = get_monotone_assignment(&channels, &targets, VoiceLeadingCost::Total);
+        let mut by_pitch: Vec<(f32, usize)> =
+            channels.iter().copied().zip(mapping.clone()).collect();
+        by_pitch.sort_by(|l, r| l.0.partial_cmp(&r.0).unwrap());
+        assert!(by_pitch.windows(2).all(|pair| pair[0].1 < pair[1].1));
+        assert_eq!(total_motion(&channels, &targets, &mapping), 7.0);
+    }
+
+    #[test]
+    fn surplus_channels_cover_every_target() {
+        let channels = [48.0, 50.0, 52.0, 67.0, 72.0];
+        let targets = [49.0, 60.0, 71.0];
+        for mapping in [
+            get_monotone_assignment(&channels, &targets, VoiceLeadingCost::Total),
+            get_unconstrained_assignment(&channels, &targets, VoiceLeadingCost::Total),
+        ] {
+            for target in 0..targets.len() {
+                assert!(mapping.contains(&target));
+            }
+            assert_eq!(total_motion(&channels, &targets, &mapping), 13.0);
+        }
+    }
+
+    #[test]
+    fn max_cost_limits_the_largest_move() {
+        let channels = [50.0, 60.0, 70.0];
+        let targets = [48.0, 58.0, 62.0, 80.0];
+        for mapping in [
+            get_monotone_assignment(&channels, &targets, VoiceLeadingCost::Max),
+            get_unconstrained_assignment(&channels, &targets, VoiceLeadingCost::Max),
+        ] {
+            assert_eq!(max_motion(&channels, &targets, &mapping), 8.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod unison_midi {
+    use super::{ChordMap, ChordMapper, Unison};
+    use crate::midi::bender::Bender;
+    use crate::midi::Note;
+
+    fn note(midi_number: u8) -> Note {
+        Note {
+            midi_number,
+            ..Default::default()
+        }
+    }
+
+    fn mapper(unison: Unison) -> ChordMapper {
+        ChordMapper {
+            chord_map: ChordMap::Unison,
+            unison,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn centroid_of_the_voices_that_meet() {
+        let channels: Vec<Bender> = [48, 60]
+            .iter()
+            .map(|&midi_number| Bender {
+                note: note(midi_number),
+                ..Default::default()
+            })
+            .collect();
+        let targets = [note(60), note(64), note(68)];
+        let converge = mapper(Unison::Converge).get_unison_midi(&channels, &targets);
+        assert_eq!(converge, Some(54.0));
+        let diverge = mapper(Unison::Diverge).get_unison_midi(&channels, &targets);
+        assert_eq!(diverge, Some(64.0));
+    }
+
+    #[test]
+    fn only_unison_has_a_unison() {
+        let targets = [note(60), note(64)];
+        let fixed = ChordMapper {
+            unison_midi: Some(62.0),
+            ..mapper(Unison::Converge)
+        };
+        assert_eq!(fixed.get_unison_midi(&[], &targets), Some(62.0));
+        let closest = ChordMapper {
+            chord_map: ChordMap::Closest,
+            ..fixed
+        };
+        assert_eq!(closest.get_unison_midi(&[], &targets), None);
+    }
+}
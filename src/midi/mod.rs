@@ -2,13 +2,19 @@ pub mod bender;
 pub mod chord;
 pub mod mapper;
 pub mod paths;
+pub mod rng;
+pub mod voicing;
 
 use std::time::Duration;
 
+use smallvec::SmallVec;
 use vst::event::MidiEvent;
 
 use crate::GLISS_EPOCH;
 
+// one item per voice or channel, never more than MAX_CHANNELS so it stays off the heap
+pub type ChannelVec<T> = SmallVec<[T; chord::MAX_CHANNELS]>;
+
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct Bend(pub u16);
 
@@ -101,6 +107,8 @@ pub struct Note {
     pub ui_time: Duration,
     pub new_note_on: bool,
     pub key_released: bool,
+    // semitones added to midi_number when doubled voices are detuned
+    pub detune: f32,
 }
 
 impl Note {
@@ -117,7 +125,7 @@ impl Note {
             144..=159 => midi_data[0] - 143,
             _ => return Err(format!("midi_data: {midi_data:?} is not note on")),
         };
-        log::info!(
+        log::debug!(
             "creatd note(channel: {channel}, midi_number: {midi_number})  from: {midi_data:?}",
             midi_number = midi_data[1]
         );
@@ -131,9 +139,14 @@ impl Note {
             ui_time,
             new_note_on: false,
             key_released: false,
+            detune: 0.0,
         })
     }
 
+    pub fn midi(&self) -> f32 {
+        self.midi_number as f32 + self.detune
+    }
+
     pub fn as_midi_event(&self) -> MidiEvent {
         MidiEvent {
             // TODO need to capture velocity instead of default 64
@@ -149,15 +162,19 @@ impl Note {
 
     // TODO or just return Bend to max?
     pub fn bend_to(&self, target: &Note, pitch_bend_range: f32) -> Result<Bend, String> {
-        let n_semitones = target.midi_number as f32 - self.midi_number as f32;
-        log::info!("bend_to n_semitones {}", n_semitones);
+        self.bend_to_midi(target.midi(), pitch_bend_range)
+    }
+
+    pub fn bend_to_midi(&self, target_midi: f32, pitch_bend_range: f32) -> Result<Bend, String> {
+        let n_semitones = target_midi - self.midi_number as f32;
+        log::debug!("bend_to n_semitones {}", n_semitones);
         let pitch_bend_ratio = n_semitones / pitch_bend_range;
-        log::info!("bend_to pitch_bend_ratio {}", pitch_bend_ratio);
+        log::debug!("bend_to pitch_bend_ratio {}", pitch_bend_ratio);
         match pitch_bend_ratio {
             ratio if (-1.0..=1.0).contains(&ratio) => {
                 // map pitch_bend_ratio
                 let midi_bend = (8192.0 * (ratio + 1.0)) as u16;
-                log::info!("bend_to midi_bend: {}", midi_bend);
+                log::debug!("bend_to midi_bend: {}", midi_bend);
                 Ok(Bend(midi_bend))
             }
             _ => Err(format!(
@@ -166,3 +183,70 @@ impl Note {
         }
     }
 }
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+// middle C, midi 60, is C4
+pub fn note_name(midi_number: u8) -> String {
+    format!(
+        "{}{}",
+        NOTE_NAMES[midi_number as usize % 12],
+        midi_number as i32 / 12 - 1
+    )
+}
+
+// the nearest note and the cents away from it
+pub fn pitch_label(midi: f32) -> String {
+    let nearest = midi.round().clamp(0.0, 127.0);
+    let cents = ((midi - nearest) * 100.0).round() as i32;
+    match cents {
+        0 => note_name(nearest as u8),
+        cents => format!("{} {cents:+}c", note_name(nearest as u8)),
+    }
+}
+
+#[cfg(test)]
+mod note_names {
+    use super::{note_name, pitch_label};
+
+    #[test]
+    fn names_and_cents() {
+        assert_eq!(note_name(60), "C4");
+        assert_eq!(note_name(0), "C-1");
+        assert_eq!(note_name(70), "A#4");
+        assert_eq!(pitch_label(64.23), "E4 +23c");
+        assert_eq!(pitch_label(63.6), "E4 -40c");
+        assert_eq!(pitch_label(69.0), "A4");
+    }
+}
+
+// notes and a clock for the bender, engine and plugin tests
+#[cfg(test)]
+pub mod fixtures {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use vst::event::MidiEvent;
+
+    pub const SECOND: f64 = 1_000_000_000.0;
+
+    pub fn note_on(midi_number: u8) -> MidiEvent {
+        MidiEvent {
+            data: [144, midi_number, 100],
+            delta_frames: 0,
+            live: true,
+            note_length: None,
+            note_offset: None,
+            detune: 0,
+            note_off_velocity: 0,
+        }
+    }
+
+    // tests start from the wall clock so note times come after GLISS_EPOCH
+    pub fn start_time() -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as f64
+    }
+}
@@ -1,5 +1,6 @@
 use rand::Rng;
 
+use crate::midi::rng::with_rng;
 use crate::midi::Bend;
 use crate::state::GlissParam::*;
 use crate::EditorState;
@@ -16,6 +17,15 @@ pub enum Path {
 }
 
 impl Path {
+    pub const ALL: [Path; 6] = [
+        Path::SCurve,
+        Path::Linear,
+        Path::Sin,
+        Path::Step,
+        Path::Triangle,
+        Path::Saw,
+    ];
+
     pub fn from_f32(val: f32) -> Self {
         match (val * 6.0) as u32 {
             0 => Path::SCurve,
@@ -131,29 +141,31 @@ impl BendPathBuilder {
     }
 
     pub fn build(&self) -> BendPath {
-        let mut rng = rand::thread_rng();
-        let path = match self.path {
-            Some(p) => p,
-            None => Path::from_f32(rng.gen()),
-        };
+        with_rng(|rng| {
+            let path = match self.path {
+                Some(p) => p,
+                None => Path::from_f32(rng.gen()),
+            };
 
-        BendPath {
-            path,
-            amplitude: rng.gen_range(
-                self.amplitude - self.amplitude_randomness
-                    ..=self.amplitude + self.amplitude_randomness,
-            ),
-            periods: (rng.gen_range(
-                self.periods - self.periods_randomness..=self.periods + self.periods_randomness,
-            ))
-            .round(),
-            s_curve_beta: rng.gen_range(
-                self.s_curve_sharpness - self.s_curve_sharpness_randomness
-                    ..=self.s_curve_sharpness + self.s_curve_sharpness_randomness,
-            ),
-            phase: rng
-                .gen_range(self.phase - self.phase_randomness..=self.phase + self.phase_randomness),
-        }
+            BendPath {
+                path,
+                amplitude: rng.gen_range(
+                    self.amplitude - self.amplitude_randomness
+                        ..=self.amplitude + self.amplitude_randomness,
+                ),
+                periods: (rng.gen_range(
+                    self.periods - self.periods_randomness..=self.periods + self.periods_randomness,
+                ))
+                .round(),
+                s_curve_beta: rng.gen_range(
+                    self.s_curve_sharpness - self.s_curve_sharpness_randomness
+                        ..=self.s_curve_sharpness + self.s_curve_sharpness_randomness,
+                ),
+                phase: rng.gen_range(
+                    self.phase - self.phase_randomness..=self.phase + self.phase_randomness,
+                ),
+            }
+        })
     }
 }
 
@@ -0,0 +1,210 @@
+use crate::midi::chord::MAX_CHANNELS;
+use crate::midi::{ChannelVec, Note};
+
+// how extra voices are added when a chord has fewer notes than the voice count
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum Doubling {
+    // +1, -1, +2, -2 ... octaves from the doubled note
+    #[default]
+    Octaves,
+    // every copy of a note is spread evenly across the detune spread
+    Detune,
+}
+
+impl Doubling {
+    pub fn from_f32(val: f32) -> Self {
+        match (val * 2.0) as u32 {
+            0 => Doubling::Octaves,
+            _ => Doubling::Detune,
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Doubling::Octaves => 0.0,
+            Doubling::Detune => 1.0 / 2.0,
+        }
+    }
+}
+
+// which notes are doubled first and dropped last
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum VoicePriority {
+    // top and bottom notes first, working inwards
+    #[default]
+    Outer,
+    Top,
+    Bottom,
+}
+
+impl VoicePriority {
+    pub fn from_f32(val: f32) -> Self {
+        match (val * 3.0) as u32 {
+            0 => VoicePriority::Outer,
+            1 => VoicePriority::Top,
+            _ => VoicePriority::Bottom,
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            VoicePriority::Outer => 0.0,
+            VoicePriority::Top => 1.0 / 3.0,
+            VoicePriority::Bottom => 2.0 / 3.0,
+        }
+    }
+
+    // note indicies from most to least important, notes are sorted by pitch
+    fn rank(&self, n_notes: usize) -> ChannelVec<usize> {
+        match self {
+            VoicePriority::Outer => (0..n_notes)
+                .map(|i| {
+                    if i % 2 == 0 {
+                        n_notes - 1 - i / 2
+                    } else {
+                        i / 2
+                    }
+                })
+                .collect(),
+            VoicePriority::Top => (0..n_notes).rev().collect(),
+            VoicePriority::Bottom => (0..n_notes).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod voicing_categorical_params {
+    use super::{Doubling, VoicePriority};
+
+    #[test]
+    fn there_and_back_again() {
+        for og_val in [0.0, 0.1, 1.0 / 3.0, 0.5, 2.0 / 3.0, 1.0].iter() {
+            let doubling = Doubling::from_f32(*og_val);
+            assert_eq!(doubling, Doubling::from_f32(doubling.as_f64() as f32));
+            let priority = VoicePriority::from_f32(*og_val);
+            assert_eq!(priority, VoicePriority::from_f32(priority.as_f64() as f32));
+        }
+    }
+}
+
+// turns the notes of a chord into the voices the bender plays
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Voicer {
+    // 0 follows the number of notes in each chord
+    pub voice_count: usize,
+    pub doubling: Doubling,
+    // semitones between the flattest and sharpest copy of a detuned note
+    pub detune_spread: f32,
+    pub priority: VoicePriority,
+}
+
+impl Voicer {
+    // voices are sorted by pitch like chord notes
+    pub fn get_voices(&self, notes: &[Note]) -> ChannelVec<Note> {
+        let n_notes = notes.len();
+        if self.voice_count == 0 || n_notes == 0 {
+            return ChannelVec::from_slice(notes);
+        }
+        let mut copies = [0; MAX_CHANNELS];
+        for note_idx in self
+            .priority
+            .rank(n_notes)
+            .into_iter()
+            .cycle()
+            .take(self.voice_count)
+        {
+            copies[note_idx] += 1;
+        }
+
+        let mut voices = ChannelVec::new();
+        for (note, n_copies) in notes.iter().zip(copies) {
+            for copy in 0..n_copies {
+                let mut voice = *note;
+                match self.doubling {
+                    Doubling::Octaves => voice.midi_number = octave_double(note.midi_number, copy),
+                    Doubling::Detune if n_copies > 1 => {
+                        voice.detune =
+                            self.detune_spread * (copy as f32 / (n_copies - 1) as f32 - 0.5)
+                    }
+                    Doubling::Detune => (),
+                }
+                voices.push(voice);
+            }
+        }
+        voices.sort_by(|a, b| a.midi().partial_cmp(&b.midi()).expect("no NANs"));
+        voices
+    }
+}
+
+// the nth copy of a note moves +1, -1, +2, -2 ... octaves, skipping octaves outside of midi
+fn octave_double(midi_number: u8, copy: usize) -> u8 {
+    let octaves = (1..=10).flat_map(|octave| [octave, -octave]);
+    std::iter::once(0)
+        .chain(octaves)
+        .map(|octave: i32| midi_number as i32 + 12 * octave)
+        .filter(|midi| (0..=127).contains(midi))
+        .nth(copy)
+        .unwrap_or(midi_number as i32) as u8
+}
+
+#[cfg(test)]
+mod get_voices {
+    use super::{Doubling, VoicePriority, Voicer};
+    use crate::midi::Note;
+
+    fn notes(midi_numbers: &[u8]) -> Vec<Note> {
+        midi_numbers
+            .iter()
+            .map(|&midi_number| Note {
+                midi_number,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn midis(voices: &[Note]) -> Vec<f32> {
+        voices.iter().map(|voice| voice.midi()).collect()
+    }
+
+    #[test]
+    fn triad_doubled_across_octaves() {
+        let voicer = Voicer {
+            voice_count: 7,
+            ..Default::default()
+        };
+        let voices = voicer.get_voices(&notes(&[60, 64, 67]));
+        assert_eq!(
+            midis(&voices),
+            vec![55.0, 60.0, 64.0, 67.0, 72.0, 76.0, 79.0]
+        );
+    }
+
+    #[test]
+    fn unison_detuned_across_the_spread() {
+        let voicer = Voicer {
+            voice_count: 4,
+            doubling: Doubling::Detune,
+            detune_spread: 0.2,
+            ..Default::default()
+        };
+        let voices = voicer.get_voices(&notes(&[60, 67]));
+        assert_eq!(voices.len(), 4);
+        for (voice, expected) in midis(&voices).iter().zip([59.9, 60.1, 66.9, 67.1]) {
+            assert!((voice - expected).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn surplus_notes_dropped_by_priority() {
+        let chord = notes(&[48, 52, 55, 59, 62]);
+        let voicer = |priority| Voicer {
+            voice_count: 3,
+            priority,
+            ..Default::default()
+        };
+        let outer = voicer(VoicePriority::Outer).get_voices(&chord);
+        assert_eq!(midis(&outer), vec![48.0, 59.0, 62.0]);
+        let bottom = voicer(VoicePriority::Bottom).get_voices(&chord);
+        assert_eq!(midis(&bottom), vec![48.0, 52.0, 55.0]);
+    }
+}
@@ -0,0 +1,301 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use vst::event::MidiEvent;
+
+use crate::engine::Engine;
+use crate::midi::rng;
+use crate::state::EditorState;
+use crate::synth::{PreviewSynth, Waveform};
+use crate::GLISS_EPOCH;
+
+const NANOS_PER_SECOND: f64 = 1_000_000_000.0;
+// events are exchanged with the engine once per block, like a host with this buffer size
+const BLOCK_SIZE: usize = 64;
+// randomized params pick the same values on every render
+const SEED: u64 = 42;
+
+pub const USAGE: &str = "usage: ui render PROGRESSION OUTPUT.wav [--preset FILE] \
+[--chord-seconds SECONDS] [--tail-seconds SECONDS] [--sample-rate HZ] [--waveform sine|saw]
+
+PROGRESSION is chords separated by '|', each chord notes like C4 Eb4 G4 or midi numbers";
+
+// everything the renderer needs, parsed from the arguments after "render"
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    pub progression: Vec<Vec<u8>>,
+    pub output: PathBuf,
+    pub preset: Option<PathBuf>,
+    pub chord_seconds: f64,
+    // rendered after the last chord is released
+    pub tail_seconds: f64,
+    pub sample_rate: u32,
+    // None uses the preset's preview synth, or a saw when it is off
+    pub waveform: Option<Waveform>,
+}
+
+impl RenderOptions {
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let mut positional = vec![];
+        let mut options = RenderOptions {
+            progression: vec![],
+            output: PathBuf::new(),
+            preset: None,
+            chord_seconds: 4.0,
+            tail_seconds: 4.0,
+            sample_rate: 44_100,
+            waveform: None,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .with_context(|| format!("{arg} needs a value\n{USAGE}"))
+            };
+            match arg.as_str() {
+                "--preset" => options.preset = Some(PathBuf::from(value()?)),
+                "--chord-seconds" => options.chord_seconds = value()?.parse()?,
+                "--tail-seconds" => options.tail_seconds = value()?.parse()?,
+                "--sample-rate" => options.sample_rate = value()?.parse()?,
+                "--waveform" => {
+                    options.waveform = match value()?.to_lowercase().as_str() {
+                        "sine" => Some(Waveform::Sine),
+                        "saw" => Some(Waveform::Saw),
+                        other => bail!("unknown waveform {other}\n{USAGE}"),
+                    }
+                }
+                flag if flag.starts_with("--") => bail!("unknown option {flag}\n{USAGE}"),
+                _ => positional.push(arg),
+            }
+        }
+        match positional.as_slice() {
+            [progression, output] => {
+                options.progression = parse_progression(progression)?;
+                options.output = PathBuf::from(output);
+            }
+            _ => bail!("{USAGE}"),
+        }
+        Ok(options)
+    }
+}
+
+// "C4 E4 G4 | 62 65 69", middle C is C4 and midi 60
+pub fn parse_progression(text: &str) -> Result<Vec<Vec<u8>>> {
+    let progression: Vec<Vec<u8>> = text
+        .split('|')
+        .map(|chord| chord.split_whitespace().map(parse_note).collect())
+        .collect::<Result<_>>()?;
+    if progression.iter().any(|chord| chord.is_empty()) {
+        bail!("empty chord in progression: {text}");
+    }
+    Ok(progression)
+}
+
+fn parse_note(text: &str) -> Result<u8> {
+    if let Ok(midi_number) = text.parse::<u8>() {
+        if midi_number <= 127 {
+            return Ok(midi_number);
+        }
+    }
+    let mut chars = text.chars();
+    let pitch_class: i32 = match chars.next().map(|c| c.to_ascii_uppercase()) {
+        Some('C') => 0,
+        Some('D') => 2,
+        Some('E') => 4,
+        Some('F') => 5,
+        Some('G') => 7,
+        Some('A') => 9,
+        Some('B') => 11,
+        _ => bail!("not a note: {text}"),
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = match rest.chars().next() {
+        Some('#') => (1, &rest[1..]),
+        Some('b') => (-1, &rest[1..]),
+        _ => (0, rest),
+    };
+    let octave: i32 = octave
+        .parse()
+        .with_context(|| format!("not a note: {text}"))?;
+    match 12 * (octave + 1) + pitch_class + accidental {
+        midi_number @ 0..=127 => Ok(midi_number as u8),
+        _ => bail!("note out of midi range: {text}"),
+    }
+}
+
+fn note_event(status: u8, midi_number: u8) -> MidiEvent {
+    MidiEvent {
+        data: [status, midi_number, 100],
+        delta_frames: 0,
+        live: false,
+        note_length: None,
+        note_offset: None,
+        detune: 0,
+        note_off_velocity: 0,
+    }
+}
+
+// runs the engine on a simulated clock, each chord is played on channel 1 for chord_seconds
+pub fn render(options: &RenderOptions) -> Result<Vec<f32>> {
+    rng::seed(SEED);
+    let state = EditorState::new();
+    state.set_parameters_to_default();
+    if let Some(preset) = &options.preset {
+        let report = state.load_preset(preset.clone())?;
+        if !report.is_complete() {
+            eprintln!("{report}");
+        }
+    }
+    let mut engine = Engine::new(&state);
+    let mut synth = PreviewSynth::new();
+    state.update_preview_synth(&mut synth);
+    synth.sample_rate = options.sample_rate as f32;
+    synth.waveform = match (options.waveform, synth.waveform) {
+        (Some(waveform), _) => waveform,
+        (None, Waveform::Off) => Waveform::Saw,
+        (None, waveform) => waveform,
+    };
+
+    // the engine measures time from the unix epoch like a live host and can't go before
+    // GLISS_EPOCH, starting on a whole second rounds every clock reading the same way each run
+    let start = (GLISS_EPOCH.as_secs() + 1) as f64 * NANOS_PER_SECOND;
+    let chord_samples = (options.chord_seconds * options.sample_rate as f64) as usize;
+    let n_samples = chord_samples * options.progression.len()
+        + (options.tail_seconds * options.sample_rate as f64) as usize;
+    let mut samples = vec![0.0; n_samples];
+    let mut playing: &[u8] = &[];
+    for (block_idx, block) in samples.chunks_mut(BLOCK_SIZE).enumerate() {
+        let sample_idx = block_idx * BLOCK_SIZE;
+        let now = start + sample_idx as f64 / options.sample_rate as f64 * NANOS_PER_SECOND;
+        let chord = options.progression.get(sample_idx / chord_samples.max(1));
+        let chord: &[u8] = chord.map(Vec::as_slice).unwrap_or(&[]);
+        if chord != playing {
+            for midi_number in playing {
+                engine.receive_midi(&state, note_event(128, *midi_number), now);
+            }
+            for midi_number in chord {
+                engine.receive_midi(&state, note_event(144, *midi_number), now);
+            }
+            playing = chord;
+        }
+        for event in engine.bend(&state, now) {
+            synth.handle_event(event.data);
+        }
+        synth.render(block);
+        // stands in for the editor, which would keep the engine's queue drained
+        drop(state.update_engine_view());
+    }
+    // thick chords sum past full scale, scale them down rather than clip
+    let peak = samples
+        .iter()
+        .fold(0.0, |peak: f32, sample| peak.max(sample.abs()));
+    if peak > 1.0 {
+        for sample in samples.iter_mut() {
+            *sample /= peak;
+        }
+    }
+    // the plugin shows these in the editor and keeps going, so does the renderer
+    if let Some(error_state) = state.error_state.lock().unwrap().as_ref() {
+        eprintln!("engine error while rendering: {}", error_state.message);
+    }
+    Ok(samples)
+}
+
+// mono 16 bit PCM
+pub fn write_wav(path: &Path, sample_rate: u32, samples: &[f32]) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    write_wav_to(&mut writer, sample_rate, samples)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_wav_to(writer: &mut impl Write, sample_rate: u32, samples: &[f32]) -> Result<()> {
+    let data_len = samples.len() as u32 * 2;
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_len).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    // PCM, mono
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    // byte rate, block align, bits per sample
+    writer.write_all(&(sample_rate * 2).to_le_bytes())?;
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())?;
+    for sample in samples {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod offline_render {
+    use super::{parse_progression, render, write_wav_to, RenderOptions};
+    use crate::synth::Waveform;
+
+    #[test]
+    fn progression_takes_names_and_numbers() {
+        let progression = parse_progression("C4 Eb4 G4 | 62 f#4 A4").unwrap();
+        assert_eq!(progression, vec![vec![60, 63, 67], vec![62, 66, 69]]);
+        assert!(parse_progression("C4 | | G4").is_err());
+        assert!(parse_progression("H4").is_err());
+    }
+
+    #[test]
+    fn options_from_args() {
+        let args: Vec<String> = ["C4 E4 | D4 F4", "out.wav", "--chord-seconds", "2"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let options = RenderOptions::from_args(&args).unwrap();
+        assert_eq!(options.progression.len(), 2);
+        assert_eq!(options.chord_seconds, 2.0);
+        assert!(RenderOptions::from_args(&args[..1]).is_err());
+    }
+
+    #[test]
+    fn renders_sound_for_every_chord() {
+        let options = RenderOptions {
+            progression: parse_progression("C4 E4 G4 | D4 F4 A4").unwrap(),
+            output: Default::default(),
+            preset: None,
+            chord_seconds: 1.0,
+            tail_seconds: 0.5,
+            sample_rate: 8_000,
+            waveform: Some(Waveform::Sine),
+        };
+        let samples = render(&options).unwrap();
+        assert_eq!(samples.len(), 20_000);
+        for chord in samples[..16_000].chunks(8_000) {
+            assert!(chord.iter().any(|sample| sample.abs() > 0.1));
+        }
+
+        let mut wav = vec![];
+        write_wav_to(&mut wav, options.sample_rate, &samples).unwrap();
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(wav.len(), 44 + 2 * samples.len());
+    }
+
+    #[test]
+    fn renders_repeat_exactly() {
+        let options = RenderOptions {
+            progression: parse_progression("C4 E4 G4 | D4 F4 A4 C5").unwrap(),
+            output: Default::default(),
+            // random mapping and bend duration randomness
+            preset: Some(concat!(env!("CARGO_MANIFEST_DIR"), "/presets/deep_note.preset").into()),
+            // the second chord arrives within the hold and its bend finishes in the tail
+            chord_seconds: 4.0,
+            tail_seconds: 8.0,
+            sample_rate: 2_000,
+            waveform: Some(Waveform::Saw),
+        };
+        assert_eq!(render(&options).unwrap(), render(&options).unwrap());
+    }
+}
@@ -1,17 +1,24 @@
-use std::io::BufRead;
+use std::io::Read;
 use std::io::Write;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
-use std::sync::Mutex;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
 
 use vst::plugin::PluginParameters;
 use vst::util::ParameterTransfer;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use rand::Rng;
 
-use crate::midi::bender::RenderedBenders;
-use crate::midi::chord::ChordBender;
-use crate::midi::mapper::ChordMap;
+use crate::engine::EngineView;
+use crate::midi::chord::SurplusVoices;
+use crate::midi::mapper::{ChordMap, MappingPreference, Unison, VoiceLeadingCost};
 use crate::midi::paths::Path;
+use crate::midi::voicing::{Doubling, VoicePriority};
+use crate::synth::{PreviewSynth, Waveform};
 
 struct Nano;
 
@@ -49,9 +56,30 @@ pub enum GlissParam {
     SawPeriodsRandomness,
     SawPhase,
     SawPhaseRandomness,
+    BendDurationRandomness,
+    BendRate,
+    BendMappingPreference,
+    OptimalMotion,
+    OptimalCrossings,
+    UnisonDirection,
+    UnisonPitch,
+    UnisonNote,
+    VoiceCount,
+    VoiceDoubling,
+    DetuneSpread,
+    VoicePriority,
+    SurplusVoices,
+    FadeController,
+    FadeDuration,
+    NewVoices,
+    PreviewWaveform,
+    PreviewAttack,
+    PreviewRelease,
+    PreviewVolume,
+    FadeLevel,
 }
 
-pub const GLISS_PARAMETERS: [GlissParam; 28] = [
+pub const GLISS_PARAMETERS: [GlissParam; 49] = [
     GlissParam::PitchBendRange,
     GlissParam::BendDuration,
     GlissParam::HoldDuration,
@@ -80,6 +108,27 @@ pub const GLISS_PARAMETERS: [GlissParam; 28] = [
     GlissParam::SawPeriodsRandomness,
     GlissParam::SawPhase,
     GlissParam::SawPhaseRandomness,
+    GlissParam::BendDurationRandomness,
+    GlissParam::BendRate,
+    GlissParam::BendMappingPreference,
+    GlissParam::OptimalMotion,
+    GlissParam::OptimalCrossings,
+    GlissParam::UnisonDirection,
+    GlissParam::UnisonPitch,
+    GlissParam::UnisonNote,
+    GlissParam::VoiceCount,
+    GlissParam::VoiceDoubling,
+    GlissParam::DetuneSpread,
+    GlissParam::VoicePriority,
+    GlissParam::SurplusVoices,
+    GlissParam::FadeController,
+    GlissParam::FadeDuration,
+    GlissParam::NewVoices,
+    GlissParam::PreviewWaveform,
+    GlissParam::PreviewAttack,
+    GlissParam::PreviewRelease,
+    GlissParam::PreviewVolume,
+    GlissParam::FadeLevel,
 ];
 
 pub struct ParamConfig {
@@ -93,6 +142,7 @@ pub struct ParamConfig {
     pub unit: &'static str,
     pub ui_name: &'static str,
     pub daw_name: &'static str,
+    // formats a value in ui units, categorical params range 0 to 1 so get their daw value
     pub daw_display: &'static dyn Fn(f32) -> String,
 }
 
@@ -110,6 +160,25 @@ impl ParamConfig {
         }
     }
 
+    // "3.5", "3.5 seconds" or "3.5 secs", clamped to the param's range
+    pub fn parse_ui_value(&self, text: &str) -> Option<f64> {
+        let text = text.trim();
+        let number_end = text
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(number_end);
+        let unit = unit.trim().to_lowercase();
+        if !self
+            .unit
+            .to_lowercase()
+            .starts_with(unit.trim_end_matches('s'))
+        {
+            return None;
+        }
+        let value: f64 = number.parse().ok()?;
+        Some(value.max(self.min).min(self.max))
+    }
+
     pub fn map_to_gliss(&self, daw_value: f32) -> f64 {
         let value = self.map_to_ui(daw_value) * self.ui_to_gliss_scalar;
         if self.is_integer {
@@ -123,6 +192,7 @@ impl ParamConfig {
 impl GlissParam {
     pub fn get_randomness_param(&self) -> Option<GlissParam> {
         match self {
+            GlissParam::BendDuration => Some(GlissParam::BendDurationRandomness),
             GlissParam::SCurveSharpness => Some(GlissParam::SCurveSharpnessRandomness),
             GlissParam::StepPeriods => Some(GlissParam::StepPeriodsRandomness),
             GlissParam::SinAmplitude => Some(GlissParam::SinAmplitudeRandomness),
@@ -138,6 +208,147 @@ impl GlissParam {
         }
     }
 
+    // the preview synth's sound, not the glissando's character
+    pub fn is_preview(&self) -> bool {
+        matches!(
+            self,
+            GlissParam::PreviewWaveform
+                | GlissParam::PreviewAttack
+                | GlissParam::PreviewRelease
+                | GlissParam::PreviewVolume
+        )
+    }
+
+    // params picking one of a few options, hosts step through them instead of sliding
+    pub fn is_categorical(&self) -> bool {
+        self.option_count().is_some()
+    }
+
+    // the enum-backed params keep their options out of get_options, the ui draws them itself
+    pub fn option_count(&self) -> Option<usize> {
+        match self {
+            GlissParam::BendMapping => Some(ChordMap::ALL.len()),
+            GlissParam::BendMappingPreference => Some(MappingPreference::ALL.len()),
+            GlissParam::BendPath => Some(Path::ALL.len()),
+            _ => self.get_options().map(|options| options.len()),
+        }
+    }
+
+    // the daw value of a categorical param's option, options are counted in plain values
+    pub fn option_daw_value(&self, option_idx: usize) -> f32 {
+        let value = match self {
+            GlissParam::BendMapping => ChordMap::ALL[option_idx].as_f64(),
+            GlissParam::BendMappingPreference => MappingPreference::ALL[option_idx].as_f64(),
+            GlissParam::BendPath => Path::ALL[option_idx].as_f64(),
+            _ => option_idx as f64 / self.option_count().unwrap_or(1) as f64,
+        };
+        value as f32
+    }
+
+    // plugin formats with plain values, the option index for categorical params and ui units for the rest
+    pub fn daw_to_plain(&self, daw_value: f32) -> f64 {
+        match self.option_count() {
+            Some(n_options) => (0..n_options)
+                .rev()
+                .find(|&option_idx| self.option_daw_value(option_idx) <= daw_value)
+                .unwrap_or(0) as f64,
+            None => self.get_config().map_to_ui(daw_value),
+        }
+    }
+
+    pub fn plain_to_daw(&self, plain: f64) -> f32 {
+        match self.option_count() {
+            Some(n_options) => {
+                self.option_daw_value((plain.round().max(0.0) as usize).min(n_options - 1))
+            }
+            None => self.get_config().map_to_daw(plain),
+        }
+    }
+
+    // categorical params take an option name, the rest a value in ui units, returns the daw value
+    pub fn parse_text(&self, text: &str) -> Option<f32> {
+        if let Some(value) = self.option_value(text) {
+            return Some(value);
+        }
+        // a word shown in place of the minimum, like Fixed or Chord
+        if text.trim().eq_ignore_ascii_case(&self.display_text(0.0)) {
+            return Some(0.0);
+        }
+        match self.get_options() {
+            Some(_) => None,
+            None => {
+                let config = self.get_config();
+                config
+                    .parse_ui_value(text)
+                    .map(|value| config.map_to_daw(value))
+            }
+        }
+    }
+
+    // what hosts show for a daw value, in the units parse_text reads back
+    pub fn display_text(&self, daw_value: f32) -> String {
+        let config = self.get_config();
+        (config.daw_display)(config.map_to_ui(daw_value) as f32)
+    }
+
+    // the option a categorical param's daw value picks, None for continuous params
+    // presets save the name so adding an option can not change what they load as
+    pub fn option_name(&self, daw_value: f32) -> Option<String> {
+        match self {
+            GlissParam::BendMapping => Some(format!("{:?}", ChordMap::from_f32(daw_value))),
+            GlissParam::BendMappingPreference => {
+                Some(format!("{:?}", MappingPreference::from_f32(daw_value)))
+            }
+            GlissParam::BendPath => Some(format!("{:?}", Path::from_f32(daw_value))),
+            _ => self
+                .get_options()
+                .map(|options| options[self.daw_to_plain(daw_value) as usize].to_string()),
+        }
+    }
+
+    // the daw value of a categorical param's option, the name matched ignoring case
+    pub fn option_value(&self, name: &str) -> Option<f32> {
+        let name = name.trim();
+        let matches =
+            |option: &dyn std::fmt::Debug| format!("{option:?}").eq_ignore_ascii_case(name);
+        let value = match self {
+            GlissParam::BendMapping => ChordMap::ALL
+                .iter()
+                .find(|chord_map| matches(*chord_map))?
+                .as_f64(),
+            GlissParam::BendMappingPreference => MappingPreference::ALL
+                .iter()
+                .find(|preference| matches(*preference))?
+                .as_f64(),
+            GlissParam::BendPath => Path::ALL.iter().find(|path| matches(*path))?.as_f64(),
+            _ => {
+                let options = self.get_options()?;
+                let option_idx = options
+                    .iter()
+                    .position(|option| option.eq_ignore_ascii_case(name))?;
+                option_idx as f64 / options.len() as f64
+            }
+        };
+        Some(value as f32)
+    }
+
+    // categorical parameters pick option i of n with the value i / n
+    pub fn get_options(&self) -> Option<&'static [&'static str]> {
+        match self {
+            GlissParam::OptimalMotion => Some(&["Total", "Max"]),
+            GlissParam::OptimalCrossings => Some(&["Allow", "Prevent"]),
+            GlissParam::UnisonDirection => Some(&["Converge", "Diverge"]),
+            GlissParam::UnisonPitch => Some(&["Centroid", "Note"]),
+            GlissParam::VoiceDoubling => Some(&["Octaves", "Detune"]),
+            GlissParam::VoicePriority => Some(&["Outer", "Top", "Bottom"]),
+            GlissParam::SurplusVoices => Some(&["Keep", "Release", "Fade"]),
+            GlissParam::FadeController => Some(&["CC7", "CC11"]),
+            GlissParam::NewVoices => Some(&["Own Pitch", "Split"]),
+            GlissParam::PreviewWaveform => Some(&["Off", "Sine", "Saw"]),
+            _ => None,
+        }
+    }
+
     pub fn get_config(&self) -> ParamConfig {
         match self {
             GlissParam::PitchBendRange => {
@@ -174,6 +385,48 @@ impl GlissParam {
                     daw_display: &|value| format!("{:.2} secs", value),
                 }
             }
+            GlissParam::BendDurationRandomness => {
+                let min = 0.0;
+                let max = 4.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: false,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: Nano::SECOND,
+                    speed: (max - min) / 100.0,
+                    unit: "seconds",
+                    ui_name: "Bend Duration Randomness",
+                    daw_name: "Bend Duration Randomness",
+                    daw_display: &|value| format!("{:.2} secs", value),
+                }
+            }
+            // zero disables rate based durations and falls back on BendDuration
+            GlissParam::BendRate => {
+                let min = 0.0;
+                let max = 48.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: false,
+                    is_semitone: false,
+                    // semitones per nanosecond
+                    ui_to_gliss_scalar: 1.0 / Nano::SECOND,
+                    speed: (max - min) / 100.0,
+                    unit: "semitones/sec",
+                    ui_name: "Bend Rate",
+                    daw_name: "Bend Rate",
+                    daw_display: &|value| {
+                        if value == 0.0 {
+                            "Fixed".to_string()
+                        } else {
+                            format!("{:.2} semitones/sec", value)
+                        }
+                    },
+                }
+            }
             GlissParam::HoldDuration => {
                 let min = 0.10;
                 let max = 8.0;
@@ -225,7 +478,7 @@ impl GlissParam {
                     daw_display: &|value| format!("{:?}", ChordMap::from_f32(value)),
                 }
             }
-            GlissParam::BendPath => {
+            GlissParam::BendMappingPreference => {
                 let min = 0.0;
                 let max = 1.0;
                 ParamConfig {
@@ -237,99 +490,113 @@ impl GlissParam {
                     ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
                     unit: "N/A",
-                    ui_name: "Path",
-                    daw_name: "Path",
-                    daw_display: &|value| format!("{:?}", Path::from_f32(value)),
+                    ui_name: "Mapping Preference",
+                    daw_name: "Mapping Preference",
+                    daw_display: &|value| format!("{:?}", MappingPreference::from_f32(value)),
                 }
             }
-            GlissParam::SCurveSharpness => {
-                let min = 1.0;
-                let max = 5.0;
+            GlissParam::OptimalMotion => {
+                let min = 0.0;
+                let max = 1.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 2.0,
+                    default: 0.0,
                     is_integer: false,
                     is_semitone: false,
                     ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "",
-                    ui_name: "Sharpness",
-                    daw_name: "S-Curve Sharpness",
-                    daw_display: &|value| format!("{:.2}", value),
+                    unit: "motion",
+                    ui_name: "Minimize",
+                    daw_name: "Optimal Mapping Cost",
+                    daw_display: &|value| format!("{:?}", VoiceLeadingCost::from_f32(value)),
                 }
             }
-            GlissParam::SCurveSharpnessRandomness => {
+            GlissParam::OptimalCrossings => {
                 let min = 0.0;
-                let max = 5.0;
+                let max = 1.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 0.0,
+                    default: 0.5,
                     is_integer: false,
                     is_semitone: false,
                     ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
                     unit: "",
-                    ui_name: "Sharpness Randomness",
-                    daw_name: "Sin Sharpness Randomness",
-                    daw_display: &|value| format!("{:.2}", value),
+                    ui_name: "Voice Crossings",
+                    daw_name: "Optimal Mapping Crossings",
+                    daw_display: &|value| {
+                        if value < 0.5 {
+                            "Allow".to_string()
+                        } else {
+                            "Prevent".to_string()
+                        }
+                    },
                 }
             }
-            GlissParam::SinAmplitude => {
+            GlissParam::UnisonDirection => {
                 let min = 0.0;
-                let max = 12.0;
+                let max = 1.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 1.0,
+                    default: 0.0,
                     is_integer: false,
-                    is_semitone: true,
-                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "semitones",
-                    ui_name: "Amplitude",
-                    daw_name: "Sin Amplitude",
-                    daw_display: &|value| format!("{:.2} semitones", value),
+                    unit: "",
+                    ui_name: "Direction",
+                    daw_name: "Unison Direction",
+                    daw_display: &|value| format!("{:?}", Unison::from_f32(value)),
                 }
             }
-            GlissParam::SinAmplitudeRandomness => {
+            // Centroid meets on the average pitch, Note meets on UnisonNote
+            GlissParam::UnisonPitch => {
                 let min = 0.0;
-                let max = 6.0;
+                let max = 1.0;
                 ParamConfig {
                     min,
                     max,
                     default: 0.0,
                     is_integer: false,
-                    is_semitone: true,
-                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "semitones",
-                    ui_name: "Amplitude Randomness",
-                    daw_name: "Sin Amplitude Randomness",
-                    daw_display: &|value| format!("{:.2} semitones", value),
+                    unit: "",
+                    ui_name: "Unison",
+                    daw_name: "Unison Pitch",
+                    daw_display: &|value| {
+                        if value < 0.5 {
+                            "Centroid".to_string()
+                        } else {
+                            "Note".to_string()
+                        }
+                    },
                 }
             }
-            GlissParam::SinPeriods => {
+            GlissParam::UnisonNote => {
                 let min = 0.0;
-                let max = 20.0;
+                let max = 127.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 4.0,
+                    default: 62.0,
                     is_integer: true,
                     is_semitone: false,
                     ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "",
-                    ui_name: "Periods",
-                    daw_name: "Sin Periods",
-                    daw_display: &|value| format!("{:.2}", value),
+                    unit: "midi",
+                    ui_name: "Unison Note",
+                    daw_name: "Unison Note",
+                    daw_display: &|value| format!("{:.0}", value),
                 }
             }
-            GlissParam::SinPeriodsRandomness => {
+            // zero follows the number of notes in each chord
+            GlissParam::VoiceCount => {
                 let min = 0.0;
-                let max = 10.0;
+                let max = 15.0;
                 ParamConfig {
                     min,
                     max,
@@ -338,13 +605,16 @@ impl GlissParam {
                     is_semitone: false,
                     ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "",
-                    ui_name: "Periods Randomness",
-                    daw_name: "Sin Periods Randomness",
-                    daw_display: &|value| format!("{:.2}", value),
+                    unit: "voices",
+                    ui_name: "Voices",
+                    daw_name: "Voice Count",
+                    daw_display: &|value| match value.round() as u8 {
+                        0 => "Chord".to_string(),
+                        voice_count => format!("{voice_count} voices"),
+                    },
                 }
             }
-            GlissParam::SinPhase => {
+            GlissParam::VoiceDoubling => {
                 let min = 0.0;
                 let max = 1.0;
                 ParamConfig {
@@ -355,115 +625,125 @@ impl GlissParam {
                     is_semitone: false,
                     ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "periods",
-                    ui_name: "Phase",
-                    daw_name: "Sin Phase",
-                    daw_display: &|value| format!("{:.2} periods", value),
+                    unit: "",
+                    ui_name: "Doubling",
+                    daw_name: "Voice Doubling",
+                    daw_display: &|value| format!("{:?}", Doubling::from_f32(value)),
                 }
             }
-            GlissParam::SinPhaseRandomness => {
+            GlissParam::DetuneSpread => {
                 let min = 0.0;
-                let max = 1.0;
+                let max = 100.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 0.0,
+                    default: 20.0,
                     is_integer: false,
                     is_semitone: false,
-                    ui_to_gliss_scalar: 1.0,
+                    // cents to semitones
+                    ui_to_gliss_scalar: 0.01,
                     speed: (max - min) / 100.0,
-                    unit: "periods",
-                    ui_name: "Phase Randomness",
-                    daw_name: "Sin Phase Randomness",
-                    daw_display: &|value| format!("{:.2} periods", value),
+                    unit: "cents",
+                    ui_name: "Detune Spread",
+                    daw_name: "Detune Spread",
+                    daw_display: &|value| format!("{:.2} cents", value),
                 }
             }
-            GlissParam::TriangleAmplitude => {
+            GlissParam::VoicePriority => {
                 let min = 0.0;
-                let max = 12.0;
+                let max = 1.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 1.0,
+                    default: 0.0,
                     is_integer: false,
-                    is_semitone: true,
-                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "semitones",
-                    ui_name: "Amplitude",
-                    daw_name: "Triangle Amplitude",
-                    daw_display: &|value| format!("{:.2} semitones", value),
+                    unit: "",
+                    ui_name: "Priority",
+                    daw_name: "Voice Priority",
+                    daw_display: &|value| format!("{:?}", VoicePriority::from_f32(value)),
                 }
             }
-            GlissParam::TriangleAmplitudeRandomness => {
+            // voices doubling a note when the next chord is smaller
+            GlissParam::SurplusVoices => {
                 let min = 0.0;
-                let max = 6.0;
+                let max = 1.0;
                 ParamConfig {
                     min,
                     max,
                     default: 0.0,
                     is_integer: false,
-                    is_semitone: true,
-                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "semitones",
-                    ui_name: "Amplitude Randomness",
-                    daw_name: "Triangle Amplitude Randomness",
-                    daw_display: &|value| format!("{:.2} semitones", value),
+                    unit: "",
+                    ui_name: "Surplus Voices",
+                    daw_name: "Surplus Voices",
+                    daw_display: &|value| format!("{:?}", SurplusVoices::from_f32(value)),
                 }
             }
-            GlissParam::TrianglePeriods => {
+            GlissParam::FadeController => {
                 let min = 0.0;
-                let max = 20.0;
+                let max = 1.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 4.0,
-                    is_integer: true,
+                    default: 0.0,
+                    is_integer: false,
                     is_semitone: false,
                     ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
                     unit: "",
-                    ui_name: "Periods",
-                    daw_name: "Triangle Periods",
-                    daw_display: &|value| format!("{:.2}", value),
+                    ui_name: "Fade With",
+                    daw_name: "Fade Controller",
+                    daw_display: &|value| {
+                        if value < 0.5 {
+                            "CC7".to_string()
+                        } else {
+                            "CC11".to_string()
+                        }
+                    },
                 }
             }
-            GlissParam::TrianglePeriodsRandomness => {
-                let min = 0.0;
-                let max = 10.0;
+            GlissParam::FadeDuration => {
+                let min = 0.05;
+                let max = 8.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 0.0,
-                    is_integer: true,
+                    default: 1.0,
+                    is_integer: false,
                     is_semitone: false,
-                    ui_to_gliss_scalar: 1.0,
+                    ui_to_gliss_scalar: Nano::SECOND,
                     speed: (max - min) / 100.0,
-                    unit: "",
-                    ui_name: "Periods Randomness",
-                    daw_name: "Triangle Periods Randomness",
-                    daw_display: &|value| format!("{:.2}", value),
+                    unit: "seconds",
+                    ui_name: "Fade Duration",
+                    daw_name: "Fade Duration",
+                    daw_display: &|value| format!("{:.2} secs", value),
                 }
             }
-            GlissParam::TrianglePhase => {
+            // the level a fade starts from and a faded channel is brought back to
+            GlissParam::FadeLevel => {
                 let min = 0.0;
-                let max = 1.0;
+                let max = 127.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 0.0,
-                    is_integer: false,
+                    default: 100.0,
+                    is_integer: true,
                     is_semitone: false,
                     ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "periods",
-                    ui_name: "Phase",
-                    daw_name: "Triangle Phase",
-                    daw_display: &|value| format!("{:.2} periods", value),
+                    unit: "",
+                    ui_name: "Fade From",
+                    daw_name: "Fade Level",
+                    daw_display: &|value| format!("{}", value.round() as u8),
                 }
             }
-            GlissParam::TrianglePhaseRandomness => {
+            // Split starts new voices on the nearest existing voice
+            GlissParam::NewVoices => {
                 let min = 0.0;
                 let max = 1.0;
                 ParamConfig {
@@ -474,81 +754,411 @@ impl GlissParam {
                     is_semitone: false,
                     ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "periods",
-                    ui_name: "Phase Randomness",
-                    daw_name: "Triangle Phase Randomness",
-                    daw_display: &|value| format!("{:.2} periods", value),
+                    unit: "",
+                    ui_name: "New Voices",
+                    daw_name: "New Voices",
+                    daw_display: &|value| {
+                        if value < 0.5 {
+                            "Own Pitch".to_string()
+                        } else {
+                            "Split".to_string()
+                        }
+                    },
                 }
             }
-            GlissParam::SawAmplitude => {
+            // the built-in synth on the audio outputs, Off leaves them alone
+            GlissParam::PreviewWaveform => {
                 let min = 0.0;
-                let max = 12.0;
+                let max = 1.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 1.0,
+                    default: 0.0,
                     is_integer: false,
-                    is_semitone: true,
-                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "semitones",
-                    ui_name: "Amplitude",
-                    daw_name: "Saw Amplitude",
-                    daw_display: &|value| format!("{:.2} semitones", value),
+                    unit: "",
+                    ui_name: "Preview Synth",
+                    daw_name: "Preview Synth",
+                    daw_display: &|value| format!("{:?}", Waveform::from_f32(value)),
                 }
             }
-            GlissParam::SawAmplitudeRandomness => {
+            GlissParam::PreviewAttack => {
                 let min = 0.0;
-                let max = 6.0;
+                let max = 2.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 0.0,
+                    default: 0.01,
                     is_integer: false,
-                    is_semitone: true,
-                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "semitones",
-                    ui_name: "Amplitude Randomness",
-                    daw_name: "Saw Amplitude Randomness",
-                    daw_display: &|value| format!("{:.2} semitones", value),
+                    unit: "seconds",
+                    ui_name: "Preview Attack",
+                    daw_name: "Preview Attack",
+                    daw_display: &|value| format!("{:.2} secs", value),
                 }
             }
-            GlissParam::SawPeriods => {
+            GlissParam::PreviewRelease => {
                 let min = 0.0;
-                let max = 20.0;
+                let max = 4.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 4.0,
-                    is_integer: true,
+                    default: 0.3,
+                    is_integer: false,
                     is_semitone: false,
                     ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
-                    unit: "",
-                    ui_name: "Periods",
-                    daw_name: "Saw Periods",
-                    daw_display: &|value| format!("{:.2}", value),
+                    unit: "seconds",
+                    ui_name: "Preview Release",
+                    daw_name: "Preview Release",
+                    daw_display: &|value| format!("{:.2} secs", value),
                 }
             }
-            GlissParam::SawPeriodsRandomness => {
+            GlissParam::PreviewVolume => {
                 let min = 0.0;
-                let max = 10.0;
+                let max = 1.0;
                 ParamConfig {
                     min,
                     max,
-                    default: 0.0,
-                    is_integer: true,
+                    default: 0.5,
+                    is_integer: false,
                     is_semitone: false,
                     ui_to_gliss_scalar: 1.0,
                     speed: (max - min) / 100.0,
                     unit: "",
-                    ui_name: "Periods Randomness",
-                    daw_name: "Saw Periods Randomness",
+                    ui_name: "Preview Volume",
+                    daw_name: "Preview Volume",
                     daw_display: &|value| format!("{:.2}", value),
                 }
             }
-            GlissParam::SawPhase => {
+            GlissParam::BendPath => {
+                let min = 0.0;
+                let max = 1.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: false,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "N/A",
+                    ui_name: "Path",
+                    daw_name: "Path",
+                    daw_display: &|value| format!("{:?}", Path::from_f32(value)),
+                }
+            }
+            GlissParam::SCurveSharpness => {
+                let min = 1.0;
+                let max = 5.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 2.0,
+                    is_integer: false,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "",
+                    ui_name: "Sharpness",
+                    daw_name: "S-Curve Sharpness",
+                    daw_display: &|value| format!("{:.2}", value),
+                }
+            }
+            GlissParam::SCurveSharpnessRandomness => {
+                let min = 0.0;
+                let max = 5.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: false,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "",
+                    ui_name: "Sharpness Randomness",
+                    daw_name: "Sin Sharpness Randomness",
+                    daw_display: &|value| format!("{:.2}", value),
+                }
+            }
+            GlissParam::SinAmplitude => {
+                let min = 0.0;
+                let max = 12.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 1.0,
+                    is_integer: false,
+                    is_semitone: true,
+                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    speed: (max - min) / 100.0,
+                    unit: "semitones",
+                    ui_name: "Amplitude",
+                    daw_name: "Sin Amplitude",
+                    daw_display: &|value| format!("{:.2} semitones", value),
+                }
+            }
+            GlissParam::SinAmplitudeRandomness => {
+                let min = 0.0;
+                let max = 6.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: false,
+                    is_semitone: true,
+                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    speed: (max - min) / 100.0,
+                    unit: "semitones",
+                    ui_name: "Amplitude Randomness",
+                    daw_name: "Sin Amplitude Randomness",
+                    daw_display: &|value| format!("{:.2} semitones", value),
+                }
+            }
+            GlissParam::SinPeriods => {
+                let min = 0.0;
+                let max = 20.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 4.0,
+                    is_integer: true,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "",
+                    ui_name: "Periods",
+                    daw_name: "Sin Periods",
+                    daw_display: &|value| format!("{:.2}", value),
+                }
+            }
+            GlissParam::SinPeriodsRandomness => {
+                let min = 0.0;
+                let max = 10.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: true,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "",
+                    ui_name: "Periods Randomness",
+                    daw_name: "Sin Periods Randomness",
+                    daw_display: &|value| format!("{:.2}", value),
+                }
+            }
+            GlissParam::SinPhase => {
+                let min = 0.0;
+                let max = 1.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: false,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "periods",
+                    ui_name: "Phase",
+                    daw_name: "Sin Phase",
+                    daw_display: &|value| format!("{:.2} periods", value),
+                }
+            }
+            GlissParam::SinPhaseRandomness => {
+                let min = 0.0;
+                let max = 1.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: false,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "periods",
+                    ui_name: "Phase Randomness",
+                    daw_name: "Sin Phase Randomness",
+                    daw_display: &|value| format!("{:.2} periods", value),
+                }
+            }
+            GlissParam::TriangleAmplitude => {
+                let min = 0.0;
+                let max = 12.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 1.0,
+                    is_integer: false,
+                    is_semitone: true,
+                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    speed: (max - min) / 100.0,
+                    unit: "semitones",
+                    ui_name: "Amplitude",
+                    daw_name: "Triangle Amplitude",
+                    daw_display: &|value| format!("{:.2} semitones", value),
+                }
+            }
+            GlissParam::TriangleAmplitudeRandomness => {
+                let min = 0.0;
+                let max = 6.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: false,
+                    is_semitone: true,
+                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    speed: (max - min) / 100.0,
+                    unit: "semitones",
+                    ui_name: "Amplitude Randomness",
+                    daw_name: "Triangle Amplitude Randomness",
+                    daw_display: &|value| format!("{:.2} semitones", value),
+                }
+            }
+            GlissParam::TrianglePeriods => {
+                let min = 0.0;
+                let max = 20.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 4.0,
+                    is_integer: true,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "",
+                    ui_name: "Periods",
+                    daw_name: "Triangle Periods",
+                    daw_display: &|value| format!("{:.2}", value),
+                }
+            }
+            GlissParam::TrianglePeriodsRandomness => {
+                let min = 0.0;
+                let max = 10.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: true,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "",
+                    ui_name: "Periods Randomness",
+                    daw_name: "Triangle Periods Randomness",
+                    daw_display: &|value| format!("{:.2}", value),
+                }
+            }
+            GlissParam::TrianglePhase => {
+                let min = 0.0;
+                let max = 1.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: false,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "periods",
+                    ui_name: "Phase",
+                    daw_name: "Triangle Phase",
+                    daw_display: &|value| format!("{:.2} periods", value),
+                }
+            }
+            GlissParam::TrianglePhaseRandomness => {
+                let min = 0.0;
+                let max = 1.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: false,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "periods",
+                    ui_name: "Phase Randomness",
+                    daw_name: "Triangle Phase Randomness",
+                    daw_display: &|value| format!("{:.2} periods", value),
+                }
+            }
+            GlissParam::SawAmplitude => {
+                let min = 0.0;
+                let max = 12.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 1.0,
+                    is_integer: false,
+                    is_semitone: true,
+                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    speed: (max - min) / 100.0,
+                    unit: "semitones",
+                    ui_name: "Amplitude",
+                    daw_name: "Saw Amplitude",
+                    daw_display: &|value| format!("{:.2} semitones", value),
+                }
+            }
+            GlissParam::SawAmplitudeRandomness => {
+                let min = 0.0;
+                let max = 6.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: false,
+                    is_semitone: true,
+                    ui_to_gliss_scalar: 8192.0, // / PITCH_BEND_RANGE as f64,
+                    speed: (max - min) / 100.0,
+                    unit: "semitones",
+                    ui_name: "Amplitude Randomness",
+                    daw_name: "Saw Amplitude Randomness",
+                    daw_display: &|value| format!("{:.2} semitones", value),
+                }
+            }
+            GlissParam::SawPeriods => {
+                let min = 0.0;
+                let max = 20.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 4.0,
+                    is_integer: true,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "",
+                    ui_name: "Periods",
+                    daw_name: "Saw Periods",
+                    daw_display: &|value| format!("{:.2}", value),
+                }
+            }
+            GlissParam::SawPeriodsRandomness => {
+                let min = 0.0;
+                let max = 10.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: true,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "",
+                    ui_name: "Periods Randomness",
+                    daw_name: "Saw Periods Randomness",
+                    daw_display: &|value| format!("{:.2}", value),
+                }
+            }
+            GlissParam::SawPhase => {
                 let min = 0.0;
                 let max = 1.0;
                 ParamConfig {
@@ -599,38 +1209,764 @@ impl GlissParam {
                     daw_display: &|value| format!("{:.2}", value),
                 }
             }
-            GlissParam::StepPeriodsRandomness => {
-                let min = 0.0;
-                let max = 10.0;
-                ParamConfig {
-                    min,
-                    max,
-                    default: 0.0,
-                    is_integer: true,
-                    is_semitone: false,
-                    ui_to_gliss_scalar: 1.0,
-                    speed: (max - min) / 100.0,
-                    unit: "",
-                    ui_name: "Periods Randomness",
-                    daw_name: "Step Periods Randomness",
-                    daw_display: &|value| format!("{:.2}", value),
-                }
+            GlissParam::StepPeriodsRandomness => {
+                let min = 0.0;
+                let max = 10.0;
+                ParamConfig {
+                    min,
+                    max,
+                    default: 0.0,
+                    is_integer: true,
+                    is_semitone: false,
+                    ui_to_gliss_scalar: 1.0,
+                    speed: (max - min) / 100.0,
+                    unit: "",
+                    ui_name: "Periods Randomness",
+                    daw_name: "Step Periods Randomness",
+                    daw_display: &|value| format!("{:.2}", value),
+                }
+            }
+        }
+    }
+}
+
+fn get_parameter_index(parameter: GlissParam) -> usize {
+    GLISS_PARAMETERS
+        .iter()
+        .position(|&p| p == parameter)
+        .expect("parameter in parameters")
+    // TODO this is more efficent?
+    //    match parameter {
+    //        GlissParam::BendDuration => 0,
+    //        GlissParam::HoldDuration => 1,
+    //        ...
+    //    }
+}
+
+// name and contents, params left out of a factory preset use their defaults
+pub const FACTORY_PRESETS: [(&str, &str); 4] = [
+    ("Default", "version = 2\n"),
+    ("Deep Note", include_str!("../presets/deep_note.preset")),
+    (
+        "Slow Cluster",
+        include_str!("../presets/slow_cluster.preset"),
+    ),
+    (
+        "Stepped Harp",
+        include_str!("../presets/stepped_harp.preset"),
+    ),
+];
+
+// bump when a GlissParam changes meaning, 0 is the original one value per line format,
+// 1 saves "GlissParam = ui value" lines and 2 saves categorical params by option name
+pub const PRESET_VERSION: u32 = 2;
+
+// what a loaded preset contained that this version does not know, and what it left out
+#[derive(Debug, Default, PartialEq)]
+pub struct PresetReport {
+    pub version: u32,
+    pub unknown: Vec<String>,
+    pub missing: Vec<GlissParam>,
+}
+
+impl PresetReport {
+    pub fn is_complete(&self) -> bool {
+        self.unknown.is_empty() && self.missing.is_empty()
+    }
+}
+
+impl std::fmt::Display for PresetReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "preset version {}", self.version)?;
+        if !self.unknown.is_empty() {
+            write!(f, ", ignored unknown keys: {}", self.unknown.join(", "))?;
+        }
+        if !self.missing.is_empty() {
+            let missing: Vec<String> = self.missing.iter().map(|p| format!("{p:?}")).collect();
+            write!(f, ", defaulted missing keys: {}", missing.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn param_from_name(name: &str) -> Option<GlissParam> {
+    GLISS_PARAMETERS
+        .iter()
+        .copied()
+        .find(|param| format!("{param:?}") == name)
+}
+
+// returns normalized values, versioned presets store "GlissParam = ui value" lines, or the option
+// name for categorical params from version 2 on, while version 0 presets store normalized values
+// one per line in GLISS_PARAMETERS order
+pub fn parse_preset(text: &str) -> Result<(Vec<(GlissParam, f32)>, PresetReport)> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let version = match lines.first().and_then(|line| line.split_once('=')) {
+        Some((key, value)) if key.trim() == "version" => value.trim().parse::<u32>()?,
+        _ => 0,
+    };
+    if version > PRESET_VERSION {
+        bail!("preset version {version} is newer than supported version {PRESET_VERSION}");
+    }
+
+    let mut values = vec![];
+    let mut report = PresetReport {
+        version,
+        ..Default::default()
+    };
+    if version == 0 {
+        for (param, line) in GLISS_PARAMETERS.iter().copied().zip(lines) {
+            values.push((param, line.parse::<f32>()?));
+        }
+    } else {
+        for line in lines.into_iter().skip(1) {
+            let (key, value) = line
+                .split_once('=')
+                .with_context(|| format!("expected key = value, found: {line}"))?;
+            let key = key.trim();
+            match param_from_name(key) {
+                // version 1 saved categorical params as their normalized value
+                Some(param) => match param.option_value(value) {
+                    Some(daw_value) => values.push((param, daw_value)),
+                    None => {
+                        let config = param.get_config();
+                        let value = value
+                            .trim()
+                            .parse::<f64>()
+                            .with_context(|| format!("{key} has an unknown value: {value}"))?;
+                        values.push((
+                            param,
+                            config.map_to_daw(value.clamp(config.min, config.max)),
+                        ));
+                    }
+                },
+                None => report.unknown.push(key.to_string()),
+            }
+        }
+    }
+    report.missing = GLISS_PARAMETERS
+        .iter()
+        .copied()
+        .filter(|param| !values.iter().any(|(found, _)| found == param))
+        .collect();
+    Ok((values, report))
+}
+
+const HISTORY_LENGTH: usize = 100;
+
+// normalized parameter snapshots, each ui gesture is one entry
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Vec<f32>>,
+    redo: Vec<Vec<f32>>,
+    // the params from before the gesture in progress
+    gesture_start: Option<Vec<f32>>,
+}
+
+impl History {
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.gesture_start.is_some()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod undo_history {
+    use super::{EditorState, GlissParam};
+
+    #[test]
+    fn undo_a_whole_gesture() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        state.end_gesture();
+        for hold_duration in [3.0, 4.0, 5.0] {
+            state.set_parameter(GlissParam::HoldDuration, hold_duration);
+        }
+        state.end_gesture();
+        state.set_parameter(GlissParam::BendDuration, 1.0);
+        state.undo();
+        assert!((state.get_ui_parameter(GlissParam::HoldDuration) - 5.0).abs() < 0.0001);
+        state.undo();
+        assert!((state.get_ui_parameter(GlissParam::HoldDuration) - 2.0).abs() < 0.0001);
+        state.redo();
+        assert!((state.get_ui_parameter(GlissParam::HoldDuration) - 5.0).abs() < 0.0001);
+    }
+}
+
+// two parameter snapshots to flip between, the active one lives in the params
+#[derive(Debug, Default)]
+pub struct AbSlots {
+    pub active: usize,
+    slots: [Option<Vec<f32>>; 2],
+}
+
+#[cfg(test)]
+mod ab_slots {
+    use super::{EditorState, GlissParam};
+
+    #[test]
+    fn flip_between_ab_slots() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        state.select_ab_slot(1);
+        state.set_parameter(GlissParam::HoldDuration, 6.0);
+        state.select_ab_slot(0);
+        assert!((state.get_ui_parameter(GlissParam::HoldDuration) - 2.0).abs() < 0.0001);
+        state.select_ab_slot(1);
+        assert!((state.get_ui_parameter(GlissParam::HoldDuration) - 6.0).abs() < 0.0001);
+    }
+}
+
+// begin_edit and end_edit bracket a ui gesture's automation so hosts record it as one touch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HostEdit {
+    Begin(GlissParam),
+    End(GlissParam),
+}
+
+impl HostEdit {
+    pub fn index(&self) -> i32 {
+        match self {
+            HostEdit::Begin(param) | HostEdit::End(param) => get_parameter_index(*param) as i32,
+        }
+    }
+}
+
+// flags for each param, the ui sets them and the audio thread takes them without locking
+#[derive(Debug)]
+pub struct HostEdits {
+    flags: [AtomicU8; GLISS_PARAMETERS.len()],
+}
+
+impl HostEdits {
+    // changed by the gesture in progress
+    const EDITING: u8 = 1;
+    // waiting for the next process call
+    const BEGIN: u8 = 2;
+    const END: u8 = 4;
+
+    // a param ended and begun again before the host heard is still one edit to the host
+    fn begin(&self, parameter: GlissParam) {
+        let flag = &self.flags[get_parameter_index(parameter)];
+        let _ = flag.fetch_update(Ordering::AcqRel, Ordering::Acquire, |flags| match flags {
+            _ if flags & Self::EDITING != 0 => None,
+            _ if flags & Self::END != 0 => Some(flags & !Self::END | Self::EDITING),
+            _ => Some(flags | Self::EDITING | Self::BEGIN),
+        });
+    }
+
+    fn end_all(&self) {
+        for flag in self.flags.iter() {
+            let _ = flag.fetch_update(Ordering::AcqRel, Ordering::Acquire, |flags| {
+                (flags & Self::EDITING != 0).then_some(flags & !Self::EDITING | Self::END)
+            });
+        }
+    }
+
+    fn take(&self) -> PendingHostEdits {
+        let mut pending = PendingHostEdits {
+            flags: [0; GLISS_PARAMETERS.len()],
+        };
+        for (flag, pending) in self.flags.iter().zip(pending.flags.iter_mut()) {
+            *pending = flag.fetch_and(Self::EDITING, Ordering::AcqRel) & !Self::EDITING;
+        }
+        pending
+    }
+}
+
+impl Default for HostEdits {
+    fn default() -> Self {
+        HostEdits {
+            flags: std::array::from_fn(|_| AtomicU8::new(0)),
+        }
+    }
+}
+
+// the edits a process call sends, begins before and ends after the automated values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PendingHostEdits {
+    flags: [u8; GLISS_PARAMETERS.len()],
+}
+
+impl PendingHostEdits {
+    fn with_flag(&self, flag: u8) -> impl Iterator<Item = GlissParam> + '_ {
+        self.flags
+            .iter()
+            .zip(GLISS_PARAMETERS)
+            .filter(move |(flags, _)| *flags & flag != 0)
+            .map(|(_, param)| param)
+    }
+
+    pub fn begun(&self) -> impl Iterator<Item = HostEdit> + '_ {
+        self.with_flag(HostEdits::BEGIN).map(HostEdit::Begin)
+    }
+
+    pub fn ended(&self) -> impl Iterator<Item = HostEdit> + '_ {
+        self.with_flag(HostEdits::END).map(HostEdit::End)
+    }
+}
+
+#[cfg(test)]
+mod host_edits {
+    use super::{EditorState, GlissParam, HostEdit};
+
+    #[test]
+    fn ui_gestures_bracket_host_automation() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        let edits = |state: &EditorState| {
+            let pending = state.take_host_edits();
+            pending
+                .begun()
+                .chain(pending.ended())
+                .collect::<Vec<HostEdit>>()
+        };
+        assert_eq!(edits(&state), vec![]);
+        for hold_duration in [3.0, 4.0] {
+            state.set_parameter(GlissParam::HoldDuration, hold_duration);
+        }
+        state.set_parameter(GlissParam::BendDuration, 1.0);
+        state.end_gesture();
+        // in param order
+        assert_eq!(
+            edits(&state),
+            vec![
+                HostEdit::Begin(GlissParam::BendDuration),
+                HostEdit::Begin(GlissParam::HoldDuration),
+                HostEdit::End(GlissParam::BendDuration),
+                HostEdit::End(GlissParam::HoldDuration),
+            ]
+        );
+
+        // a gesture that ends and starts again before the host hears stays one edit
+        state.set_parameter(GlissParam::HoldDuration, 5.0);
+        assert_eq!(
+            edits(&state),
+            vec![HostEdit::Begin(GlissParam::HoldDuration)]
+        );
+        state.end_gesture();
+        state.set_parameter(GlissParam::HoldDuration, 6.0);
+        assert_eq!(edits(&state), vec![]);
+        state.end_gesture();
+        assert_eq!(edits(&state), vec![HostEdit::End(GlissParam::HoldDuration)]);
+    }
+}
+
+// hardware controllers assigned to params, a CC sweeps its param's whole range
+// atomics so the audio thread learns and follows controllers without locking
+#[derive(Debug)]
+pub struct MidiLearn {
+    // the next CC received is assigned to this param, as its index + 1 or 0 for none
+    learning: AtomicU8,
+    // the param each controller drives, as its index + 1 or 0 for none
+    controllers: [AtomicU8; 128],
+}
+
+impl Default for MidiLearn {
+    fn default() -> Self {
+        MidiLearn {
+            learning: AtomicU8::new(0),
+            controllers: std::array::from_fn(|_| AtomicU8::new(0)),
+        }
+    }
+}
+
+impl MidiLearn {
+    fn encode(param: Option<GlissParam>) -> u8 {
+        param.map_or(0, |param| get_parameter_index(param) as u8 + 1)
+    }
+
+    fn decode(value: u8) -> Option<GlissParam> {
+        (value as usize)
+            .checked_sub(1)
+            .and_then(|index| GLISS_PARAMETERS.get(index).copied())
+    }
+
+    pub fn learning(&self) -> Option<GlissParam> {
+        Self::decode(self.learning.load(Ordering::Acquire))
+    }
+
+    // learning the same param again cancels it
+    fn toggle_learning(&self, param: GlissParam) {
+        let encoded = Self::encode(Some(param));
+        let _ = self
+            .learning
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |learning| {
+                Some(if learning == encoded { 0 } else { encoded })
+            });
+    }
+
+    pub fn controller_of(&self, param: GlissParam) -> Option<u8> {
+        let encoded = Self::encode(Some(param));
+        self.controllers
+            .iter()
+            .position(|mapped| mapped.load(Ordering::Acquire) == encoded)
+            .map(|controller| controller as u8)
+    }
+
+    pub fn mappings(&self) -> Vec<(u8, GlissParam)> {
+        self.controllers
+            .iter()
+            .enumerate()
+            .filter_map(|(controller, mapped)| {
+                Some((
+                    controller as u8,
+                    Self::decode(mapped.load(Ordering::Acquire))?,
+                ))
+            })
+            .collect()
+    }
+
+    pub fn set_mappings(&self, mappings: &[(u8, GlissParam)]) {
+        for mapped in self.controllers.iter() {
+            mapped.store(0, Ordering::Release);
+        }
+        for (controller, param) in mappings {
+            if let Some(mapped) = self.controllers.get(*controller as usize) {
+                mapped.store(Self::encode(Some(*param)), Ordering::Release);
+            }
+        }
+    }
+
+    // a controller drives one param, learning a param replaces its previous controller
+    fn receive_cc(&self, controller: u8) -> Option<GlissParam> {
+        let mapped = self.controllers.get(controller as usize)?;
+        let learning = self.learning.swap(0, Ordering::AcqRel);
+        if learning != 0 {
+            for other in self.controllers.iter() {
+                let _ = other.compare_exchange(learning, 0, Ordering::AcqRel, Ordering::Acquire);
+            }
+            mapped.store(learning, Ordering::Release);
+        }
+        Self::decode(mapped.load(Ordering::Acquire))
+    }
+}
+
+#[cfg(test)]
+mod midi_learn {
+    use super::{EditorState, GlissParam};
+    use vst::plugin::PluginParameters;
+
+    #[test]
+    fn learned_controller_sweeps_the_param_range() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        state.learn_midi(GlissParam::BendDuration);
+        state.receive_midi_cc(74, 127);
+        let max = GlissParam::BendDuration.get_config().max;
+        assert!((state.get_ui_parameter(GlissParam::BendDuration) - max).abs() < 0.0001);
+        state.receive_midi_cc(1, 0);
+        assert!((state.get_ui_parameter(GlissParam::BendDuration) - max).abs() < 0.0001);
+
+        let restored = EditorState::new();
+        restored.load_preset_data(&state.get_preset_data());
+        assert_eq!(
+            restored.midi_learn.mappings(),
+            vec![(74, GlissParam::BendDuration)]
+        );
+
+        // learning a param again moves it to the new controller
+        state.learn_midi(GlissParam::BendDuration);
+        state.receive_midi_cc(1, 0);
+        assert_eq!(
+            state.midi_learn.controller_of(GlissParam::BendDuration),
+            Some(1)
+        );
+        assert_eq!(state.midi_learn.mappings().len(), 1);
+    }
+}
+
+// the octaves drawn by the timeline and piano, octave n starts at midi number (n + 1) * 12
+// so octave -1 holds midi 0 to 11, like the note names
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyRange {
+    pub octaves: RangeInclusive<i8>,
+    // follow the sounding voices instead of the octaves the user picked
+    pub auto_fit: bool,
+}
+
+impl Default for KeyRange {
+    fn default() -> Self {
+        KeyRange {
+            octaves: 3..=4,
+            auto_fit: false,
+        }
+    }
+}
+
+impl KeyRange {
+    pub const MIN_OCTAVE: i8 = -1;
+    // holds midi 120 to 127
+    pub const MAX_OCTAVE: i8 = 9;
+    // auto fit never zooms in further than this
+    const MIN_FIT_OCTAVES: i8 = 2;
+
+    pub fn octave_of(midi_number: u8) -> i8 {
+        (midi_number / 12) as i8 - 1
+    }
+
+    pub fn visible(&self, midi_numbers: &[u8]) -> RangeInclusive<i8> {
+        let (lowest, highest) = match (midi_numbers.iter().min(), midi_numbers.iter().max()) {
+            (Some(lowest), Some(highest)) if self.auto_fit => {
+                (Self::octave_of(*lowest), Self::octave_of(*highest))
+            }
+            _ => return self.octaves.clone(),
+        };
+        let missing = (Self::MIN_FIT_OCTAVES - (highest - lowest + 1)).max(0);
+        let lowest = (lowest - missing / 2).max(Self::MIN_OCTAVE);
+        let highest = (lowest + Self::MIN_FIT_OCTAVES - 1).max(highest);
+        if highest > Self::MAX_OCTAVE {
+            (Self::MAX_OCTAVE + 1 - Self::MIN_FIT_OCTAVES)..=Self::MAX_OCTAVE
+        } else {
+            lowest..=highest
+        }
+    }
+
+    // keeps at least one octave
+    pub fn set_octaves(&mut self, lowest: i8, highest: i8) {
+        let highest = highest.clamp(Self::MIN_OCTAVE, Self::MAX_OCTAVE);
+        self.octaves = lowest.clamp(Self::MIN_OCTAVE, highest)..=highest;
+    }
+}
+
+#[cfg(test)]
+mod key_range {
+    use super::{EditorState, KeyRange};
+    use vst::plugin::PluginParameters;
+
+    #[test]
+    fn key_range_fits_the_voices() {
+        let mut key_range = KeyRange::default();
+        assert_eq!(key_range.visible(&[20, 100]), 3..=4);
+        key_range.auto_fit = true;
+        assert_eq!(key_range.visible(&[]), 3..=4);
+        // C1 to C7
+        assert_eq!(key_range.visible(&[24, 96]), 1..=7);
+        // a single octave is padded to two
+        assert_eq!(key_range.visible(&[60, 64, 67]), 4..=5);
+        assert_eq!(key_range.visible(&[127]), 8..=9);
+        // midi 0 to 11 are octave -1
+        assert_eq!(key_range.visible(&[0]), -1..=0);
+        assert_eq!(key_range.visible(&[0, 11]), -1..=0);
+
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        state.key_range.lock().unwrap().set_octaves(5, 2);
+        state.key_range.lock().unwrap().auto_fit = true;
+        let restored = EditorState::new();
+        restored.load_preset_data(&state.get_preset_data());
+        assert_eq!(
+            *restored.key_range.lock().unwrap(),
+            KeyRange {
+                octaves: 2..=2,
+                auto_fit: true
+            }
+        );
+    }
+}
+
+// how far the timeline is zoomed and scrolled, frozen stops it following the clock
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineView {
+    // shown across the whole timeline
+    pub seconds: f64,
+    // how far the present line is behind the clock, or the frozen time
+    pub scroll_back: f64,
+    // ui time when the view was frozen
+    pub frozen_at: Option<Duration>,
+}
+
+impl Default for TimelineView {
+    fn default() -> Self {
+        TimelineView {
+            seconds: 8.0,
+            scroll_back: 0.0,
+            frozen_at: None,
+        }
+    }
+}
+
+impl TimelineView {
+    // shown behind the present line however far in the timeline is zoomed
+    const MIN_HISTORY: f64 = 1.0;
+    const MAX_SECONDS: f64 = 60.0;
+    const MAX_SCROLL_BACK: f64 = 60.0;
+
+    // the bend ahead of the present line and the history behind it always fit
+    fn min_seconds(bend_seconds: f64) -> f64 {
+        (bend_seconds + Self::MIN_HISTORY).min(Self::MAX_SECONDS)
+    }
+
+    pub fn zoom(&mut self, factor: f64, bend_seconds: f64) {
+        self.seconds = (self.visible_seconds(bend_seconds) * factor)
+            .clamp(Self::min_seconds(bend_seconds), Self::MAX_SECONDS);
+    }
+
+    // widened while the bend is longer than the zoom allows
+    pub fn visible_seconds(&self, bend_seconds: f64) -> f64 {
+        self.seconds.max(Self::min_seconds(bend_seconds))
+    }
+
+    pub fn scroll(&mut self, seconds: f64) {
+        self.scroll_back = (self.scroll_back + seconds).clamp(0.0, Self::MAX_SCROLL_BACK);
+    }
+
+    // unfreezing jumps back to the present
+    pub fn toggle_freeze(&mut self, ui_now: Duration) {
+        self.frozen_at = match self.frozen_at {
+            Some(_) => {
+                self.scroll_back = 0.0;
+                None
             }
+            None => Some(ui_now),
+        };
+    }
+
+    // ui time at the timeline's present line
+    pub fn present(&self, ui_now: Duration) -> Duration {
+        self.frozen_at
+            .unwrap_or(ui_now)
+            .saturating_sub(Duration::from_secs_f64(self.scroll_back))
+    }
+}
+
+#[cfg(test)]
+mod timeline_view {
+    use super::TimelineView;
+    use std::time::Duration;
+
+    #[test]
+    fn frozen_timeline_stays_put() {
+        let mut view = TimelineView::default();
+        let now = Duration::from_secs(100);
+        assert_eq!(view.present(now), now);
+        view.toggle_freeze(now);
+        view.scroll(2.5);
+        view.scroll(-0.5);
+        let later = Duration::from_secs(130);
+        assert_eq!(view.present(later), Duration::from_secs(98));
+        view.scroll(-10.0);
+        assert_eq!(view.present(later), now);
+        view.zoom(1000.0, 1.0);
+        assert_eq!(view.seconds, 60.0);
+        view.toggle_freeze(later);
+        assert_eq!(view.present(later), later);
+    }
+
+    #[test]
+    fn zoom_keeps_the_bend_and_some_history() {
+        let mut view = TimelineView::default();
+        view.zoom(0.001, 0.5);
+        assert_eq!(view.seconds, 1.5);
+        // a longer bend widens the view without zooming it
+        assert_eq!(view.visible_seconds(8.0), 9.0);
+        view.zoom(0.001, 8.0);
+        assert_eq!(view.seconds, 9.0);
+        assert_eq!(view.visible_seconds(0.5), 9.0);
+    }
+}
+
+// an overwrite or delete waiting for the user to confirm it
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresetConfirm {
+    Overwrite(PathBuf),
+    Delete(PathBuf),
+}
+
+// where presets are saved and which one was loaded last
+#[derive(Debug)]
+pub struct PresetBrowser {
+    pub dir: PathBuf,
+    pub loaded: Option<PathBuf>,
+    pub confirm: Option<PresetConfirm>,
+    // what list found last, None once the folder may have changed
+    listing: Option<Vec<PathBuf>>,
+}
+
+impl Default for PresetBrowser {
+    fn default() -> Self {
+        Self {
+            dir: default_preset_dir(),
+            loaded: None,
+            confirm: None,
+            listing: None,
         }
     }
 }
 
-fn get_parameter_index(parameter: GlissParam) -> usize {
-    GLISS_PARAMETERS
-        .iter()
-        .position(|&p| p == parameter)
-        .expect("parameter in parameters")
-    // TODO this is more efficent?
-    //    match parameter {
-    //        GlissParam::BendDuration => 0,
-    //        GlissParam::HoldDuration => 1,
-    //        ...
-    //    }
+pub fn default_preset_dir() -> PathBuf {
+    dirs::data_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_default()
+        .join("DeepGliss")
+        .join("presets")
+}
+
+impl PresetBrowser {
+    // every preset under dir including subfolders, sorted so folders group together
+    pub fn list(&self) -> Result<Vec<PathBuf>> {
+        let mut presets = vec![];
+        if !self.dir.is_dir() {
+            return Ok(presets);
+        }
+        let mut dirs = vec![self.dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            for element in std::fs::read_dir(dir)? {
+                let path = element?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if path.extension() == Some("preset".as_ref()) {
+                    presets.push(path);
+                }
+            }
+        }
+        presets.sort();
+        Ok(presets)
+    }
+
+    // the cached list, only walking the folder again after mark_stale
+    pub fn listing(&mut self) -> Result<&[PathBuf]> {
+        if self.listing.is_none() {
+            self.listing = Some(self.list()?);
+        }
+        Ok(self.listing.as_deref().unwrap_or_default())
+    }
+
+    pub fn mark_stale(&mut self) {
+        self.listing = None;
+    }
+
+    // names may include subfolders, such as "pads/deep note"
+    pub fn path_for(&self, name: &str) -> Result<PathBuf> {
+        let name = name.trim().trim_end_matches(".preset");
+        if name.is_empty() {
+            bail!("preset name is empty");
+        }
+        let relative = std::path::Path::new(name);
+        if !relative
+            .components()
+            .all(|component| matches!(component, std::path::Component::Normal(_)))
+        {
+            bail!("preset name {name} must stay inside the preset folder");
+        }
+        Ok(self.dir.join(format!("{name}.preset")))
+    }
+
+    // the path relative to dir without its extension, how the ui shows a preset
+    pub fn name_of(&self, path: &std::path::Path) -> String {
+        let relative = path.strip_prefix(&self.dir).unwrap_or(path);
+        relative
+            .with_extension("")
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
 }
 
 pub struct ErrorState {
@@ -650,31 +1986,48 @@ impl ErrorState {
 pub struct EditorState {
     pub params: Arc<ParameterTransfer>,
     pub editor_params: Arc<Mutex<Vec<GlissParam>>>,
-    pub chord_bender: Arc<Mutex<ChordBender>>,
-    pub rendered_benders: Arc<Mutex<RenderedBenders>>,
+    // only the ui locks this, the engine publishes to it through a queue
+    pub engine_view: Arc<Mutex<EngineView>>,
     pub keyboard_focus: Arc<Mutex<Option<Path>>>,
     pub error_state: Arc<Mutex<Option<ErrorState>>>,
+    // the host saves state from its own thread while the ui loads presets, so neither
+    // preset_filename nor preset_browser is locked while the other is held
     pub preset_filename: Arc<Mutex<String>>,
+    // index into FACTORY_PRESETS, the VST program
+    pub program: Arc<Mutex<usize>>,
+    pub preset_browser: Arc<Mutex<PresetBrowser>>,
+    // left alone by randomize_parameters
+    pub locked_params: Arc<Mutex<Vec<GlissParam>>>,
+    pub history: Arc<Mutex<History>>,
+    pub ab_slots: Arc<Mutex<AbSlots>>,
+    pub midi_learn: Arc<MidiLearn>,
+    pub host_edits: Arc<HostEdits>,
+    pub key_range: Arc<Mutex<KeyRange>>,
+    pub timeline_view: Arc<Mutex<TimelineView>>,
 }
 
 impl Default for EditorState {
     fn default() -> Self {
-        let init_time = std::time::Instant::now();
         EditorState {
             // TODO i dont think we need to clone anymore
             params: Arc::new(ParameterTransfer::new(GLISS_PARAMETERS.len())),
             editor_params: Arc::new(Mutex::new(vec![GlissParam::SCurveSharpness])),
-            chord_bender: Arc::new(Mutex::new(ChordBender::new(
-                init_time,
-                Nano::SECOND * GlissParam::BendDuration.get_config().default,
-                Nano::SECOND * GlissParam::HoldDuration.get_config().default,
-                GlissParam::PitchBendRange.get_config().default as f32,
-                Nano::SECOND * GlissParam::ChordCaptureDuration.get_config().default,
-            ))),
-            rendered_benders: Arc::new(Mutex::new(RenderedBenders::new())),
+            engine_view: Arc::new(Mutex::new(EngineView::default())),
             keyboard_focus: Arc::new(Mutex::new(None)),
             error_state: Arc::new(Mutex::new(None)),
             preset_filename: Arc::new(Mutex::new("my_filename".to_string())),
+            program: Arc::new(Mutex::new(0)),
+            preset_browser: Arc::new(Mutex::new(PresetBrowser::default())),
+            locked_params: Arc::new(Mutex::new(vec![
+                GlissParam::PitchBendRange,
+                GlissParam::ChordCaptureDuration,
+            ])),
+            history: Arc::new(Mutex::new(History::default())),
+            ab_slots: Arc::new(Mutex::new(AbSlots::default())),
+            midi_learn: Arc::new(MidiLearn::default()),
+            host_edits: Arc::new(HostEdits::default()),
+            key_range: Arc::new(Mutex::new(KeyRange::default())),
+            timeline_view: Arc::new(Mutex::new(TimelineView::default())),
         }
     }
 }
@@ -705,12 +2058,21 @@ impl EditorState {
     }
 
     pub fn set_parameter(&self, parameter: GlissParam, val: f64) {
+        self.begin_gesture();
+        self.begin_host_edit(parameter);
         let daw_value = parameter.get_config().map_to_daw(val);
         let index = get_parameter_index(parameter);
         self.params.set_parameter(index, daw_value)
     }
 
     pub fn set_parameter_to_default(&self, parameter: GlissParam) {
+        self.begin_gesture();
+        self.begin_host_edit(parameter);
+        self.reset_parameter(parameter);
+    }
+
+    // not a ui edit, nothing is sent to the host but the new value
+    fn reset_parameter(&self, parameter: GlissParam) {
         let config = parameter.get_config();
         let value = config.map_to_daw(config.default);
         let index = get_parameter_index(parameter);
@@ -718,33 +2080,387 @@ impl EditorState {
     }
 
     pub fn set_parameters_to_default(&self) {
+        self.begin_gesture();
+        for param in GLISS_PARAMETERS {
+            self.reset_parameter(param);
+        }
+    }
+
+    // the first ui edit of a param within a gesture opens a host edit
+    fn begin_host_edit(&self, parameter: GlissParam) {
+        self.host_edits.begin(parameter);
+    }
+
+    // sent by the audio thread, begins before and ends after the automated values
+    pub fn take_host_edits(&self) -> PendingHostEdits {
+        self.host_edits.take()
+    }
+
+    fn snapshot(&self) -> Vec<f32> {
+        (0..GLISS_PARAMETERS.len())
+            .map(|index| self.params.get_parameter(index))
+            .collect()
+    }
+
+    fn restore(&self, snapshot: &[f32]) {
+        for (index, value) in snapshot.iter().enumerate() {
+            self.params.set_parameter(index, *value);
+        }
+    }
+
+    // ui edits call this before changing params, the first call of a gesture saves the params
+    pub fn begin_gesture(&self) {
+        let mut history = self.history.lock().unwrap();
+        if history.gesture_start.is_none() {
+            history.gesture_start = Some(self.snapshot());
+        }
+    }
+
+    // called once the pointer is released, gestures that changed nothing are dropped
+    pub fn end_gesture(&self) {
+        self.host_edits.end_all();
+        let mut history = self.history.lock().unwrap();
+        if let Some(gesture_start) = history.gesture_start.take() {
+            if gesture_start != self.snapshot() {
+                history.undo.push(gesture_start);
+                if history.undo.len() > HISTORY_LENGTH {
+                    history.undo.remove(0);
+                }
+                history.redo.clear();
+            }
+        }
+    }
+
+    pub fn undo(&self) {
+        self.end_gesture();
+        let mut history = self.history.lock().unwrap();
+        if let Some(snapshot) = history.undo.pop() {
+            history.redo.push(self.snapshot());
+            self.restore(&snapshot);
+        }
+    }
+
+    pub fn redo(&self) {
+        self.end_gesture();
+        let mut history = self.history.lock().unwrap();
+        if let Some(snapshot) = history.redo.pop() {
+            history.undo.push(self.snapshot());
+            self.restore(&snapshot);
+        }
+    }
+
+    // stores the params in the active slot and loads the other, an empty slot starts as a copy
+    pub fn select_ab_slot(&self, slot: usize) {
+        let mut ab_slots = self.ab_slots.lock().unwrap();
+        if ab_slots.active == slot {
+            return;
+        }
+        self.begin_gesture();
+        let active = ab_slots.active;
+        ab_slots.slots[active] = Some(self.snapshot());
+        if let Some(snapshot) = &ab_slots.slots[slot] {
+            self.restore(snapshot);
+        }
+        ab_slots.active = slot;
+    }
+
+    // right-clicking a param again cancels learning
+    pub fn learn_midi(&self, parameter: GlissParam) {
+        self.midi_learn.toggle_learning(parameter);
+    }
+
+    // called from the audio thread
+    pub fn receive_midi_cc(&self, controller: u8, value: u8) {
+        if let Some(param) = self.midi_learn.receive_cc(controller) {
+            let index = get_parameter_index(param);
+            self.params.set_parameter(index, value as f32 / 127.0);
+        }
+    }
+
+    pub fn toggle_lock(&self, parameter: GlissParam) {
+        let mut locked_params = self.locked_params.lock().unwrap();
+        match locked_params.iter().position(|locked| *locked == parameter) {
+            Some(idx) => {
+                locked_params.remove(idx);
+            }
+            None => locked_params.push(parameter),
+        }
+    }
+
+    // the dice, every unlocked param gets a uniformly random value within its range
+    pub fn randomize_parameters(&self) {
+        self.begin_gesture();
+        let locked_params = self.locked_params.lock().unwrap();
+        let mut rng = rand::thread_rng();
+        // a roll never switches on audio in a session routing midi to an instrument
         for param in GLISS_PARAMETERS {
-            self.set_parameter_to_default(param);
+            if !locked_params.contains(&param) && !param.is_preview() {
+                let index = get_parameter_index(param);
+                self.params.set_parameter(index, rng.gen_range(0.0..=1.0));
+            }
+        }
+    }
+
+    pub fn update_preview_synth(&self, synth: &mut PreviewSynth) {
+        synth.waveform = Waveform::from_f32(self.get_parameter(GlissParam::PreviewWaveform));
+        synth.attack = self.get_gliss_parameter(GlissParam::PreviewAttack) as f32;
+        synth.release = self.get_gliss_parameter(GlissParam::PreviewRelease) as f32;
+        synth.volume = self.get_gliss_parameter(GlissParam::PreviewVolume) as f32;
+        synth.pitch_bend_range = self.get_gliss_parameter(GlissParam::PitchBendRange) as f32;
+    }
+
+    // the ui's copy of the engine, brought up to date with what it published since the last call
+    pub fn update_engine_view(&self) -> MutexGuard<'_, EngineView> {
+        let mut engine_view = self.engine_view.lock().unwrap();
+        if let Some(message) = engine_view.update() {
+            *self.error_state.lock().unwrap() = Some(ErrorState::new(message));
         }
+        engine_view
     }
 
     pub fn save_parameters(&self, mut file: std::fs::File) -> Result<()> {
+        writeln!(file, "version = {PRESET_VERSION}")?;
+        self.write_parameters(&mut file)
+    }
+
+    fn write_parameters(&self, writer: &mut impl Write) -> Result<()> {
         for param in GLISS_PARAMETERS {
-            let value = self.get_parameter(param);
-            writeln!(file, "{value}")?;
-            log::info!("writing param: {param:?} to: {value}");
+            match param.option_name(self.get_parameter(param)) {
+                Some(name) => writeln!(writer, "{param:?} = {name}")?,
+                None => writeln!(writer, "{param:?} = {}", self.get_ui_parameter(param))?,
+            }
         }
         Ok(())
     }
 
-    pub fn load_parameters(&self, file: std::fs::File) -> Result<()> {
-        let mut reader = std::io::BufReader::new(file);
-        for param in GLISS_PARAMETERS {
-            let mut line = String::new();
-            reader.read_line(&mut line)?;
-            line.pop();
-            log::info!("read line after newline removed: {line}");
-            let value = line.parse::<f32>()?;
+    // refuses to replace an existing preset unless overwrite is set
+    pub fn save_preset(&self, name: &str, overwrite: bool) -> Result<()> {
+        let mut browser = self.preset_browser.lock().unwrap();
+        let path = browser.path_for(name)?;
+        if path.exists() && !overwrite {
+            browser.confirm = Some(PresetConfirm::Overwrite(path));
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.save_parameters(std::fs::File::create(&path)?)?;
+        browser.loaded = Some(path);
+        browser.confirm = None;
+        browser.mark_stale();
+        Ok(())
+    }
+
+    pub fn load_preset(&self, path: PathBuf) -> Result<PresetReport> {
+        self.begin_gesture();
+        let report = self.load_parameters(std::fs::File::open(&path)?)?;
+        let name = {
+            let mut browser = self.preset_browser.lock().unwrap();
+            let name = browser.name_of(&path);
+            browser.loaded = Some(path);
+            browser.confirm = None;
+            name
+        };
+        *self.preset_filename.lock().unwrap() = name;
+        Ok(report)
+    }
+
+    pub fn rename_preset(&self, from: &std::path::Path, name: &str) -> Result<()> {
+        let mut browser = self.preset_browser.lock().unwrap();
+        let to = browser.path_for(name)?;
+        if to.exists() {
+            bail!("preset {} already exists", browser.name_of(&to));
+        }
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(from, &to)?;
+        if browser.loaded.as_deref() == Some(from) {
+            browser.loaded = Some(to);
+        }
+        browser.mark_stale();
+        Ok(())
+    }
+
+    pub fn delete_preset(&self, path: &std::path::Path) -> Result<()> {
+        std::fs::remove_file(path)?;
+        let mut browser = self.preset_browser.lock().unwrap();
+        if browser.loaded.as_deref() == Some(path) {
+            browser.loaded = None;
+        }
+        browser.confirm = None;
+        browser.mark_stale();
+        Ok(())
+    }
+
+    pub fn load_parameters(&self, mut file: std::fs::File) -> Result<PresetReport> {
+        let mut text = String::new();
+        file.read_to_string(&mut text)?;
+        self.apply_preset(&text)
+    }
+
+    // params the preset does not mention are set to their defaults
+    fn apply_preset(&self, text: &str) -> Result<PresetReport> {
+        let report = self.apply_preset_values(text)?;
+        for param in report.missing.iter() {
+            self.reset_parameter(*param);
+        }
+        Ok(report)
+    }
+
+    // params the preset does not mention are left as they are
+    fn apply_preset_values(&self, text: &str) -> Result<PresetReport> {
+        let (values, report) = parse_preset(text)?;
+        for (param, value) in values {
             log::info!("setting param: {param:?} to parsed value: {value}");
             let index = get_parameter_index(param);
             self.params.set_parameter(index, value)
         }
-        Ok(())
+        Ok(report)
+    }
+
+    // a preset plus the settings hosts cannot automate, saved in the DAW project
+    pub fn write_state(&self, writer: &mut impl Write) -> Result<()> {
+        writeln!(writer, "version = {PRESET_VERSION}")?;
+        let preset_filename = self.preset_filename.lock().unwrap().clone();
+        writeln!(writer, "preset_filename = {preset_filename}")?;
+        let program = *self.program.lock().unwrap();
+        writeln!(writer, "program = {program}")?;
+        let preset_dir = self.preset_browser.lock().unwrap().dir.clone();
+        writeln!(writer, "preset_dir = {}", preset_dir.display())?;
+        let editor_params: Vec<String> = self
+            .editor_params
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|param| format!("{param:?}"))
+            .collect();
+        writeln!(writer, "editor_params = {}", editor_params.join(", "))?;
+        let locked_params: Vec<String> = self
+            .locked_params
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|param| format!("{param:?}"))
+            .collect();
+        writeln!(writer, "locked_params = {}", locked_params.join(", "))?;
+        let midi_mappings: Vec<String> = self
+            .midi_learn
+            .mappings()
+            .iter()
+            .map(|(controller, param)| format!("{controller} {param:?}"))
+            .collect();
+        writeln!(writer, "midi_mappings = {}", midi_mappings.join(", "))?;
+        let key_range = self.key_range.lock().unwrap().clone();
+        let auto_fit = if key_range.auto_fit { " auto" } else { "" };
+        writeln!(
+            writer,
+            "key_range = {} {}{auto_fit}",
+            key_range.octaves.start(),
+            key_range.octaves.end()
+        )?;
+        self.write_parameters(writer)
+    }
+
+    pub fn load_state(&self, text: &str) -> Result<PresetReport> {
+        let mut preset = String::new();
+        for line in text.lines() {
+            match line.split_once('=') {
+                Some((key, value)) if key.trim() == "preset_filename" => {
+                    *self.preset_filename.lock().unwrap() = value.trim().to_string();
+                }
+                Some((key, value)) if key.trim() == "preset_dir" => {
+                    let mut browser = self.preset_browser.lock().unwrap();
+                    browser.dir = PathBuf::from(value.trim());
+                    browser.mark_stale();
+                }
+                // a bad line only loses its own setting, the params below it still load
+                Some((key, value)) if key.trim() == "program" => match value.trim().parse() {
+                    Ok(program) => *self.program.lock().unwrap() = program,
+                    Err(e) => log::warn!("skipping program {value:?}: {e}"),
+                },
+                Some((key, value)) if key.trim() == "locked_params" => {
+                    *self.locked_params.lock().unwrap() = value
+                        .split(',')
+                        .filter_map(|name| param_from_name(name.trim()))
+                        .collect();
+                }
+                Some((key, value)) if key.trim() == "midi_mappings" => {
+                    let mappings: Vec<(u8, GlissParam)> = value
+                        .split(',')
+                        .filter_map(|mapping| {
+                            let (controller, name) = mapping.trim().split_once(' ')?;
+                            Some((controller.parse().ok()?, param_from_name(name)?))
+                        })
+                        .collect();
+                    self.midi_learn.set_mappings(&mappings);
+                }
+                Some((key, value)) if key.trim() == "key_range" => {
+                    let mut key_range = self.key_range.lock().unwrap();
+                    let mut words = value.split_whitespace();
+                    match (words.next().map(str::parse), words.next().map(str::parse)) {
+                        (Some(Ok(lowest)), Some(Ok(highest))) => {
+                            key_range.set_octaves(lowest, highest)
+                        }
+                        _ => log::warn!("skipping key_range {value:?}"),
+                    }
+                    key_range.auto_fit = words.next() == Some("auto");
+                }
+                Some((key, value)) if key.trim() == "editor_params" => {
+                    *self.editor_params.lock().unwrap() = value
+                        .split(',')
+                        .filter_map(|name| param_from_name(name.trim()))
+                        .collect();
+                }
+                _ => {
+                    preset.push_str(line);
+                    preset.push('\n');
+                }
+            }
+        }
+        self.apply_preset(&preset)
+    }
+}
+
+#[cfg(test)]
+mod dice {
+    use super::{EditorState, GlissParam, GLISS_PARAMETERS};
+
+    #[test]
+    fn dice_leaves_locked_params_alone() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        *state.locked_params.lock().unwrap() = GLISS_PARAMETERS[1..].to_vec();
+        state.toggle_lock(GlissParam::HoldDuration);
+        let before: Vec<f32> = GLISS_PARAMETERS
+            .iter()
+            .map(|param| state.get_parameter(*param))
+            .collect();
+        state.randomize_parameters();
+        for (param, value) in GLISS_PARAMETERS.iter().zip(before) {
+            let unlocked = [GLISS_PARAMETERS[0], GlissParam::HoldDuration].contains(param);
+            if !unlocked {
+                assert_eq!(state.get_parameter(*param), value);
+            }
+            assert!((0.0..=1.0).contains(&state.get_parameter(*param)));
+        }
+    }
+
+    #[test]
+    fn dice_leaves_the_preview_synth_alone() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        state.locked_params.lock().unwrap().clear();
+        for _ in 0..10 {
+            state.randomize_parameters();
+            for param in GLISS_PARAMETERS.iter().filter(|param| param.is_preview()) {
+                let config = param.get_config();
+                assert_eq!(
+                    state.get_parameter(*param),
+                    config.map_to_daw(config.default)
+                );
+            }
+        }
     }
 }
 
@@ -757,9 +2473,72 @@ impl PluginParameters for EditorState {
         self.params.set_parameter(index as usize, val);
     }
 
+    fn change_preset(&self, preset: i32) {
+        let (name, text) = match FACTORY_PRESETS.get(preset as usize) {
+            Some(factory_preset) => factory_preset,
+            None => return,
+        };
+        // the bend range matches the downstream synth, so programs keep what they leave out
+        if let Err(e) = self.apply_preset_values(text) {
+            log::error!("failed to load factory preset {name}: {e}");
+            return;
+        }
+        *self.program.lock().unwrap() = preset as usize;
+        *self.preset_filename.lock().unwrap() = name.to_string();
+    }
+
+    fn get_preset_num(&self) -> i32 {
+        *self.program.lock().unwrap() as i32
+    }
+
+    fn get_preset_name(&self, preset: i32) -> String {
+        FACTORY_PRESETS
+            .get(preset as usize)
+            .map(|(name, _)| name.to_string())
+            .unwrap_or_default()
+    }
+
+    fn get_preset_data(&self) -> Vec<u8> {
+        let mut data = vec![];
+        if let Err(e) = self.write_state(&mut data) {
+            log::error!("failed to save plugin state: {e}");
+        }
+        data
+    }
+
+    fn get_bank_data(&self) -> Vec<u8> {
+        self.get_preset_data()
+    }
+
+    fn load_preset_data(&self, data: &[u8]) {
+        match self.load_state(&String::from_utf8_lossy(data)) {
+            Ok(report) if !report.is_complete() => log::warn!("loaded plugin state: {report}"),
+            Ok(_) => (),
+            Err(e) => log::error!("failed to load plugin state: {e}"),
+        }
+    }
+
+    fn load_bank_data(&self, data: &[u8]) {
+        self.load_preset_data(data)
+    }
+
+    fn string_to_parameter(&self, index: i32, text: String) -> bool {
+        let param = match GLISS_PARAMETERS.get(index as usize) {
+            Some(param) => *param,
+            None => return false,
+        };
+        match param.parse_text(&text) {
+            Some(value) => {
+                self.params.set_parameter(index as usize, value);
+                true
+            }
+            None => false,
+        }
+    }
+
     fn get_parameter_text(&self, index: i32) -> String {
         let value = self.params.get_parameter(index as usize);
-        (GLISS_PARAMETERS[index as usize].get_config().daw_display)(value)
+        GLISS_PARAMETERS[index as usize].display_text(value)
     }
 
     fn get_parameter_name(&self, index: i32) -> String {
@@ -769,3 +2548,287 @@ impl PluginParameters for EditorState {
             .to_string()
     }
 }
+
+#[cfg(test)]
+mod preset_format {
+    use super::{
+        parse_preset, EditorState, GlissParam, PresetBrowser, FACTORY_PRESETS, GLISS_PARAMETERS,
+    };
+    use crate::midi::chord::SurplusVoices;
+    use crate::midi::mapper::ChordMap;
+    use vst::plugin::PluginParameters;
+
+    #[test]
+    fn original_presets_load_by_position() {
+        let mut text = String::new();
+        for idx in 0..28 {
+            let value = if idx == 4 { 2.0 / 3.0 } else { 0.5 };
+            text.push_str(&format!("{value}\n"));
+        }
+        let (values, report) = parse_preset(&text).unwrap();
+        assert_eq!(report.version, 0);
+        assert_eq!(values.len(), 28);
+        assert_eq!(values[1], (GlissParam::BendDuration, 0.5));
+        // Random was the last of three chord maps
+        assert_eq!(values[4].0, GlissParam::BendMapping);
+        assert_eq!(ChordMap::from_f32(values[4].1), ChordMap::Random);
+        assert_eq!(report.missing, GLISS_PARAMETERS[28..].to_vec());
+    }
+
+    #[test]
+    fn versioned_presets_load_by_name() {
+        let text = "version = 1\nHoldDuration = 8\nPitchBendRange = 2\nFromTheFuture = 1\n";
+        let (values, report) = parse_preset(text).unwrap();
+        assert_eq!(
+            values,
+            vec![
+                (GlissParam::HoldDuration, 1.0),
+                (GlissParam::PitchBendRange, 0.0)
+            ]
+        );
+        assert_eq!(report.unknown, vec!["FromTheFuture".to_string()]);
+        assert_eq!(report.missing.len(), GLISS_PARAMETERS.len() - 2);
+        assert!(!report.is_complete());
+    }
+
+    #[test]
+    fn categorical_params_save_by_name() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        state.set_parameter(GlissParam::BendMapping, ChordMap::Random.as_f64());
+        state.set_parameter(GlissParam::SurplusVoices, 2.0 / 3.0);
+        let mut text = vec![];
+        state.write_parameters(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("BendMapping = Random\n"));
+        assert!(text.contains("SurplusVoices = Fade\n"));
+        assert!(text.contains("BendPath = SCurve\n"));
+
+        let (values, report) = parse_preset(&format!("version = 2\n{text}")).unwrap();
+        assert!(report.is_complete());
+        assert!(values.contains(&(GlissParam::SurplusVoices, 2.0 / 3.0)));
+        assert!(parse_preset("version = 2\nSurplusVoices = Vanish\n").is_err());
+    }
+
+    #[test]
+    fn version_1_categorical_values_still_load() {
+        let text = "version = 1\nBendMapping = 0.6666667\nSurplusVoices = 0.33333334\n";
+        let (values, _) = parse_preset(text).unwrap();
+        assert_eq!(ChordMap::from_f32(values[0].1), ChordMap::Random);
+        assert_eq!(SurplusVoices::from_f32(values[1].1), SurplusVoices::Release);
+    }
+
+    #[test]
+    fn factory_presets_only_use_known_params() {
+        for (name, text) in FACTORY_PRESETS.iter() {
+            let (_, report) = parse_preset(text).unwrap();
+            assert!(report.unknown.is_empty(), "{} has unknown keys", name);
+        }
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        assert!(parse_preset("version = 99\n").is_err());
+    }
+
+    #[test]
+    fn programs_keep_the_params_they_leave_out() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        state.set_parameter(GlissParam::PitchBendRange, 12.0);
+        state.set_parameter(GlissParam::PreviewVolume, 0.1);
+        for (program, (_, text)) in FACTORY_PRESETS.iter().enumerate() {
+            state.change_preset(program as i32);
+            assert_eq!(state.get_preset_num(), program as i32);
+            let (values, _) = parse_preset(text).unwrap();
+            for (param, value) in [
+                (GlissParam::PitchBendRange, 12.0),
+                (GlissParam::PreviewVolume, 0.1),
+            ] {
+                if values.iter().all(|(listed, _)| *listed != param) {
+                    assert!((state.get_ui_parameter(param) - value).abs() < 0.0001);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn plugin_state_round_trip() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        state.set_parameter(GlissParam::HoldDuration, 4.0);
+        *state.preset_filename.lock().unwrap() = "deep note".to_string();
+        *state.editor_params.lock().unwrap() = vec![GlissParam::VoiceCount, GlissParam::NewVoices];
+
+        let restored = EditorState::new();
+        restored.load_preset_data(&state.get_preset_data());
+        assert_eq!(*restored.preset_filename.lock().unwrap(), "deep note");
+        assert_eq!(
+            *restored.editor_params.lock().unwrap(),
+            vec![GlissParam::VoiceCount, GlissParam::NewVoices]
+        );
+        for param in GLISS_PARAMETERS {
+            let diff = state.get_ui_parameter(param) - restored.get_ui_parameter(param);
+            assert!(diff.abs() < 0.0001, "{:?} changed", param);
+        }
+    }
+
+    #[test]
+    fn bad_state_lines_keep_the_params() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        state.set_parameter(GlissParam::HoldDuration, 4.0);
+        let mut data = vec![];
+        state.write_state(&mut data).unwrap();
+        let text = String::from_utf8(data)
+            .unwrap()
+            .lines()
+            .map(|line| match line.split_once('=') {
+                Some(("program ", _)) => "program = third".to_string(),
+                Some(("key_range ", _)) => "key_range = low 5".to_string(),
+                _ => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(text.contains("program = third") && text.contains("key_range = low 5"));
+
+        let restored = EditorState::new();
+        restored.load_state(&text).unwrap();
+        assert!((restored.get_ui_parameter(GlissParam::HoldDuration) - 4.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn preset_browser_lists_subfolders() {
+        let dir = std::env::temp_dir().join(format!("gliss_presets_{}", std::process::id()));
+        let browser = PresetBrowser {
+            dir: dir.clone(),
+            ..Default::default()
+        };
+        for name in ["b", "pads/a", "pads/warm/c"] {
+            let path = browser.path_for(name).unwrap();
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "version = 1\n").unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+        let names: Vec<String> = browser
+            .list()
+            .unwrap()
+            .iter()
+            .map(|path| browser.name_of(path))
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(names, vec!["b", "pads/a", "pads/warm/c"]);
+        assert!(browser.path_for("../escape").is_err());
+        assert!(browser.path_for(" ").is_err());
+    }
+
+    #[test]
+    fn preset_listing_waits_to_be_marked_stale() {
+        let dir = std::env::temp_dir().join(format!("gliss_listing_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut browser = PresetBrowser {
+            dir: dir.clone(),
+            ..Default::default()
+        };
+        std::fs::write(dir.join("a.preset"), "version = 2\n").unwrap();
+        assert_eq!(browser.listing().unwrap().len(), 1);
+        std::fs::write(dir.join("b.preset"), "version = 2\n").unwrap();
+        assert_eq!(browser.listing().unwrap().len(), 1);
+        browser.mark_stale();
+        let found = browser.listing().unwrap().len();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, 2);
+    }
+}
+
+#[cfg(test)]
+mod typed_values {
+    use super::{EditorState, GlissParam, GLISS_PARAMETERS};
+    use vst::plugin::PluginParameters;
+
+    #[test]
+    fn typed_values_use_the_param_unit() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        let index = |param| GLISS_PARAMETERS.iter().position(|p| *p == param).unwrap() as i32;
+        assert!(state.string_to_parameter(index(GlissParam::HoldDuration), "3.5 secs".to_string()));
+        assert!((state.get_ui_parameter(GlissParam::HoldDuration) - 3.5).abs() < 0.0001);
+        assert_eq!(
+            state.get_parameter_text(index(GlissParam::HoldDuration)),
+            "3.50 secs"
+        );
+        let text = state.get_parameter_text(index(GlissParam::HoldDuration));
+        assert!(state.string_to_parameter(index(GlissParam::HoldDuration), text));
+        assert!((state.get_ui_parameter(GlissParam::HoldDuration) - 3.5).abs() < 0.0001);
+        assert!(state.string_to_parameter(index(GlissParam::HoldDuration), "4".to_string()));
+        assert!((state.get_ui_parameter(GlissParam::HoldDuration) - 4.0).abs() < 0.0001);
+        assert!(!state.string_to_parameter(index(GlissParam::HoldDuration), "4 cents".to_string()));
+        assert!(state.string_to_parameter(index(GlissParam::UnisonPitch), "note".to_string()));
+        assert_eq!(state.get_parameter(GlissParam::UnisonPitch), 0.5);
+        assert_eq!(
+            state.get_parameter_text(index(GlissParam::UnisonPitch)),
+            "Note"
+        );
+        state.set_parameter(GlissParam::UnisonNote, 60.0);
+        assert_eq!(
+            state.get_parameter_text(index(GlissParam::UnisonNote)),
+            "60"
+        );
+    }
+}
+
+#[cfg(test)]
+mod host_text {
+    use super::{get_parameter_index, EditorState, GlissParam};
+    use vst::plugin::PluginParameters;
+
+    fn text(state: &EditorState, param: GlissParam) -> String {
+        param.display_text(state.get_parameter(param))
+    }
+
+    // the host sends the shown text back and gets the same value
+    fn round_trip(state: &EditorState, param: GlissParam) {
+        let before = state.get_parameter(param);
+        let index = get_parameter_index(param) as i32;
+        assert!(state.string_to_parameter(index, text(state, param)));
+        assert!((state.get_parameter(param) - before).abs() < 0.0001);
+    }
+
+    #[test]
+    fn bend_rate_and_randomness_in_ui_units() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        assert_eq!(text(&state, GlissParam::BendRate), "Fixed");
+        state.set_parameter(GlissParam::BendRate, 12.0);
+        assert_eq!(text(&state, GlissParam::BendRate), "12.00 semitones/sec");
+        round_trip(&state, GlissParam::BendRate);
+        state.set_parameter(GlissParam::BendRate, 0.0);
+        round_trip(&state, GlissParam::BendRate);
+        state.set_parameter(GlissParam::BendDurationRandomness, 1.5);
+        assert_eq!(
+            text(&state, GlissParam::BendDurationRandomness),
+            "1.50 secs"
+        );
+    }
+
+    #[test]
+    fn voice_count_and_detune_in_ui_units() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        assert_eq!(text(&state, GlissParam::VoiceCount), "Chord");
+        round_trip(&state, GlissParam::VoiceCount);
+        state.set_parameter(GlissParam::VoiceCount, 4.0);
+        assert_eq!(text(&state, GlissParam::VoiceCount), "4 voices");
+        round_trip(&state, GlissParam::VoiceCount);
+        assert_eq!(text(&state, GlissParam::DetuneSpread), "20.00 cents");
+    }
+
+    #[test]
+    fn fade_in_ui_units() {
+        let state = EditorState::new();
+        state.set_parameters_to_default();
+        assert_eq!(text(&state, GlissParam::FadeDuration), "1.00 secs");
+        assert_eq!(text(&state, GlissParam::FadeController), "CC7");
+        assert_eq!(text(&state, GlissParam::SurplusVoices), "Keep");
+    }
+}
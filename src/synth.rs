@@ -0,0 +1,316 @@
+use std::f32::consts::TAU;
+
+// the preview synth's oscillator, Off leaves the audio outputs untouched
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum Waveform {
+    #[default]
+    Off,
+    Sine,
+    Saw,
+}
+
+impl Waveform {
+    pub fn from_f32(val: f32) -> Self {
+        match (val * 3.0) as u32 {
+            0 => Waveform::Off,
+            1 => Waveform::Sine,
+            _ => Waveform::Saw,
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Waveform::Off => 0.0,
+            Waveform::Sine => 1.0 / 3.0,
+            Waveform::Saw => 2.0 / 3.0,
+        }
+    }
+
+    // phase in 0..1
+    fn sample(&self, phase: f32) -> f32 {
+        match self {
+            Waveform::Off => 0.0,
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Saw => 2.0 * phase - 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod waveform_categorical_params {
+    use super::Waveform;
+
+    #[test]
+    fn there_and_back_again() {
+        for og_val in [0.0, 0.1, 1.0 / 3.0, 0.5, 2.0 / 3.0, 1.0].iter() {
+            let waveform = Waveform::from_f32(*og_val);
+            assert_eq!(waveform, Waveform::from_f32(waveform.as_f64() as f32));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Voice {
+    channel: usize,
+    midi_number: u8,
+    velocity: f32,
+    // 0..1 through one period
+    phase: f32,
+    // the envelope
+    level: f32,
+    released: bool,
+}
+
+// what the benders have sent to each midi channel
+#[derive(Debug, Clone, Copy)]
+struct Channel {
+    // semitones
+    bend: f32,
+    // CC7 and CC11, scaled 0..1
+    volume: f32,
+    expression: f32,
+}
+
+impl Default for Channel {
+    fn default() -> Self {
+        Channel {
+            bend: 0.0,
+            volume: 1.0,
+            expression: 1.0,
+        }
+    }
+}
+
+// a sounding and a releasing voice on each channel the benders use, past it voices are stolen
+const VOICES_CAPACITY: usize = 32;
+
+// samples reserved for rendering before the host says how long its blocks are
+pub const RENDER_CAPACITY: usize = 1024;
+
+// a polyphonic synth playing the same events the benders send, to audition without an instrument
+#[derive(Debug, Clone)]
+pub struct PreviewSynth {
+    pub sample_rate: f32,
+    pub waveform: Waveform,
+    // seconds
+    pub attack: f32,
+    pub release: f32,
+    pub volume: f32,
+    // semitones, must match the bender to decode its pitch bends
+    pub pitch_bend_range: f32,
+    channels: [Channel; 16],
+    voices: [Option<Voice>; VOICES_CAPACITY],
+}
+
+impl Default for PreviewSynth {
+    fn default() -> Self {
+        PreviewSynth {
+            sample_rate: 44_100.0,
+            waveform: Waveform::Off,
+            attack: 0.01,
+            release: 0.3,
+            volume: 0.5,
+            pitch_bend_range: 48.0,
+            channels: [Channel::default(); 16],
+            voices: [None; VOICES_CAPACITY],
+        }
+    }
+}
+
+impl PreviewSynth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn handle_event(&mut self, data: [u8; 3]) {
+        let channel = (data[0] & 0x0F) as usize;
+        match data[0] {
+            // note off
+            128..=143 => self.release(channel, data[1]),
+            // note on, velocity 0 is a note off
+            144..=159 if data[2] == 0 => self.release(channel, data[1]),
+            144..=159 => {
+                self.release(channel, data[1]);
+                *self.free_voice() = Some(Voice {
+                    channel,
+                    midi_number: data[1],
+                    velocity: data[2] as f32 / 127.0,
+                    phase: 0.0,
+                    level: 0.0,
+                    released: false,
+                });
+            }
+            // control change
+            176..=191 => match data[1] {
+                7 => self.channels[channel].volume = data[2] as f32 / 127.0,
+                11 => self.channels[channel].expression = data[2] as f32 / 127.0,
+                _ => (),
+            },
+            // pitch bend
+            224..=239 => {
+                let u14 = ((data[2] as u16) << 7) | data[1] as u16;
+                self.channels[channel].bend =
+                    (u14 as f32 - 8192.0) / 8192.0 * self.pitch_bend_range;
+            }
+            _ => (),
+        }
+    }
+
+    // an empty slot, or else the quietest voice, preferring released ones
+    fn free_voice(&mut self) -> &mut Option<Voice> {
+        let index = self
+            .voices
+            .iter()
+            .position(Option::is_none)
+            .unwrap_or_else(|| {
+                let mut quietest = 0;
+                for (i, voice) in self.voices.iter().enumerate() {
+                    let (voice, current) = (voice.unwrap(), self.voices[quietest].unwrap());
+                    if (voice.released, -voice.level) > (current.released, -current.level) {
+                        quietest = i;
+                    }
+                }
+                quietest
+            });
+        &mut self.voices[index]
+    }
+
+    fn release(&mut self, channel: usize, midi_number: u8) {
+        for voice in self.voices.iter_mut().flatten() {
+            if voice.channel == channel && voice.midi_number == midi_number {
+                voice.released = true;
+            }
+        }
+    }
+
+    // adds the synth to a mono buffer
+    pub fn render(&mut self, output: &mut [f32]) {
+        if self.waveform == Waveform::Off {
+            self.voices = [None; VOICES_CAPACITY];
+            return;
+        }
+        let attack_step = 1.0 / (self.attack * self.sample_rate).max(1.0);
+        let release_step = 1.0 / (self.release * self.sample_rate).max(1.0);
+        for voice in self.voices.iter_mut().flatten() {
+            let channel = self.channels[voice.channel];
+            let frequency =
+                440.0 * 2f32.powf((voice.midi_number as f32 + channel.bend - 69.0) / 12.0);
+            let phase_step = frequency / self.sample_rate;
+            let gain = self.volume * voice.velocity * channel.volume * channel.expression;
+            for sample in output.iter_mut() {
+                voice.level = if voice.released {
+                    (voice.level - release_step).max(0.0)
+                } else {
+                    (voice.level + attack_step).min(1.0)
+                };
+                *sample += self.waveform.sample(voice.phase) * voice.level * gain;
+                voice.phase = (voice.phase + phase_step).fract();
+            }
+        }
+        for slot in self.voices.iter_mut() {
+            if slot.is_some_and(|voice| voice.released && voice.level == 0.0) {
+                *slot = None;
+            }
+        }
+    }
+
+    // renders into scratch a chunk at a time, each written out at its offset in the block
+    // chunks never outgrow scratch's capacity, so a block longer than the host promised never allocates
+    pub fn render_chunks(
+        &mut self,
+        scratch: &mut Vec<f32>,
+        samples: usize,
+        mut write: impl FnMut(usize, &[f32]),
+    ) {
+        let chunk_len = scratch.capacity().max(1);
+        let mut offset = 0;
+        while offset < samples {
+            scratch.clear();
+            scratch.resize(chunk_len.min(samples - offset), 0.0);
+            self.render(scratch);
+            write(offset, scratch);
+            offset += scratch.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod preview_synth {
+    use super::{PreviewSynth, Waveform, VOICES_CAPACITY};
+
+    fn peak(synth: &mut PreviewSynth) -> f32 {
+        let mut output = vec![0.0; 4410];
+        synth.render(&mut output);
+        output
+            .iter()
+            .fold(0.0, |peak: f32, sample| peak.max(sample.abs()))
+    }
+
+    #[test]
+    fn notes_sound_until_released() {
+        let mut synth = PreviewSynth {
+            waveform: Waveform::Sine,
+            volume: 1.0,
+            release: 0.05,
+            ..Default::default()
+        };
+        synth.handle_event([144, 60, 127]);
+        assert!(peak(&mut synth) > 0.9);
+        synth.handle_event([128, 60, 0]);
+        peak(&mut synth);
+        assert_eq!(peak(&mut synth), 0.0);
+    }
+
+    #[test]
+    fn full_pool_steals_the_quietest_voice() {
+        let mut synth = PreviewSynth {
+            waveform: Waveform::Sine,
+            ..Default::default()
+        };
+        for midi_number in 0..VOICES_CAPACITY as u8 {
+            synth.handle_event([144, midi_number, 127]);
+        }
+        peak(&mut synth);
+        synth.handle_event([128, 5, 0]);
+        synth.handle_event([144, 100, 127]);
+        let notes: Vec<u8> = synth
+            .voices
+            .iter()
+            .flatten()
+            .map(|voice| voice.midi_number)
+            .collect();
+        assert_eq!(notes.len(), VOICES_CAPACITY);
+        assert!(notes.contains(&100));
+        assert!(!notes.contains(&5));
+    }
+
+    #[test]
+    fn long_blocks_render_in_chunks() {
+        let mut synth = PreviewSynth {
+            waveform: Waveform::Sine,
+            volume: 1.0,
+            ..Default::default()
+        };
+        synth.handle_event([144, 60, 127]);
+        let mut scratch = Vec::with_capacity(64);
+        let capacity = scratch.capacity();
+        let mut output = vec![0.0; 150];
+        synth.render_chunks(&mut scratch, output.len(), |offset, chunk| {
+            output[offset..offset + chunk.len()].copy_from_slice(chunk)
+        });
+        assert_eq!(scratch.capacity(), capacity);
+        assert!(output[140..].iter().any(|sample| *sample != 0.0));
+    }
+
+    #[test]
+    fn pitch_bends_follow_the_bend_range() {
+        let mut synth = PreviewSynth {
+            pitch_bend_range: 2.0,
+            ..Default::default()
+        };
+        synth.handle_event([225, 0, 0]);
+        assert_eq!(synth.channels[1].bend, -2.0);
+        synth.handle_event([225, 0, 64]);
+        assert_eq!(synth.channels[1].bend, 0.0);
+    }
+}
@@ -1,11 +1,11 @@
 use crate::draw::parameter_editor::draw_parameter_editor;
 use crate::draw::piano;
 use crate::draw::timeline::Timeline;
-use crate::midi::mapper::ChordMap;
+use crate::midi::mapper::{ChordMap, MappingPreference};
 use crate::midi::paths::{BendPath as BendPather, Path};
 use crate::midi::Note;
 use crate::state::GlissParam::*;
-use crate::state::{EditorState, ErrorState};
+use crate::state::{EditorState, ErrorState, TimelineView};
 
 use std::sync::Arc;
 use std::time::Duration;
@@ -17,8 +17,14 @@ use vst::editor::Editor;
 
 use egui::{vec2, Color32, CtxRef, Pos2, Rect};
 
-use crate::draw::button::{draw_linesegment, draw_map_button, draw_path_button};
-use crate::draw::preset::{draw_load_preset, draw_save_preset};
+use crate::draw::button::{
+    draw_linesegment, draw_map_button, draw_path_button, draw_preference_button,
+};
+use crate::draw::history::draw_history;
+use crate::draw::key_range::draw_key_range;
+use crate::draw::preset::{
+    draw_load_preset, draw_manage_preset, draw_preset_folder, draw_save_preset,
+};
 use crate::draw::theme::GLISS_THEME;
 use crate::GLISS_EPOCH;
 
@@ -48,18 +54,18 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
             queue.request_repaint();
 
             //let mut keyboard_focus = state.keyboard_focus.lock().unwrap();
-            let chord_bender = state.chord_bender.lock().unwrap();
+            let engine_view = state.update_engine_view();
             let ui_now = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .expect("non negative time")
                 - *GLISS_EPOCH;
 
             // TODO pass notes around by reference to avoid this clone?
-            let notes: Vec<Note> = chord_bender
+            let notes: Vec<Note> = engine_view
                 .chords
                 .iter()
                 .filter(|chord| chord.sent_to_bender)
-                .flat_map(|chord| chord.notes.clone())
+                .flat_map(|chord| chord.voices.clone())
                 .collect();
             log::debug!("n_notes: {}", notes.len());
 
@@ -76,11 +82,44 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                     let response = ui.add(egui::widgets::Button::new("Settings"));
                     if response.clicked() {
                         let mut editor_params = state.editor_params.lock().unwrap();
-                        *editor_params = vec![PitchBendRange, ChordCaptureDuration];
+                        *editor_params = vec![
+                            PitchBendRange,
+                            ChordCaptureDuration,
+                            PreviewWaveform,
+                            PreviewAttack,
+                            PreviewRelease,
+                            PreviewVolume,
+                        ];
                     }
                     if response.double_clicked() {
                         state.set_parameter_to_default(PitchBendRange);
                         state.set_parameter_to_default(ChordCaptureDuration);
+                        state.set_parameter_to_default(PreviewWaveform);
+                        state.set_parameter_to_default(PreviewAttack);
+                        state.set_parameter_to_default(PreviewRelease);
+                        state.set_parameter_to_default(PreviewVolume);
+                    }
+                    let response = ui.add(egui::widgets::Button::new("Voices"));
+                    if response.clicked() {
+                        let mut editor_params = state.editor_params.lock().unwrap();
+                        *editor_params = vec![
+                            VoiceCount,
+                            VoiceDoubling,
+                            DetuneSpread,
+                            VoicePriority,
+                            NewVoices,
+                        ];
+                    }
+                    if response.double_clicked() {
+                        state.set_parameter_to_default(VoiceCount);
+                        state.set_parameter_to_default(VoiceDoubling);
+                        state.set_parameter_to_default(DetuneSpread);
+                        state.set_parameter_to_default(VoicePriority);
+                        state.set_parameter_to_default(NewVoices);
+                    }
+                    let response = ui.add(egui::widgets::Button::new("Dice"));
+                    if response.clicked() {
+                        state.randomize_parameters();
                     }
                     let response = ui.add(egui::widgets::Button::new("Presets"));
                     if response.clicked() {
@@ -90,7 +129,7 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                 });
 
                 let mut x1 = 92.0;
-                let mut x2 = x1 + 50.0;
+                let mut x2 = x1 + 42.0;
                 let y1 = 25.0;
                 let y2 = 75.0;
                 ui.vertical(|ui| {
@@ -118,11 +157,12 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                             state,
                             ChordMap::Closest,
                             val == ChordMap::Closest,
+                            vec![],
                         );
 
                         // flipped v1
-                        x1 += 60.0;
-                        x2 += 60.0;
+                        x1 += 48.0;
+                        x2 += 48.0;
                         let to_rect = egui::Rect::from_x_y_ranges(x1..=x2, y1..=y2);
                         let line1 = [Pos2::new(1.0, 1.0), Pos2::new(5.0, 5.0)];
                         let line2 = [Pos2::new(1.0, 3.0), Pos2::new(5.0, 3.0)];
@@ -138,11 +178,12 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                             state,
                             ChordMap::Flipped,
                             val == ChordMap::Flipped,
+                            vec![],
                         );
 
                         //random v1
-                        x1 += 60.0;
-                        x2 += 60.0;
+                        x1 += 48.0;
+                        x2 += 48.0;
                         let to_rect = egui::Rect::from_x_y_ranges(x1..=x2, y1..=y2);
                         let line1 = [Pos2::new(1.0, 1.0), Pos2::new(2.0, 2.0)];
                         let line2 = [Pos2::new(1.0, 3.0), Pos2::new(2.0, 3.0)];
@@ -164,7 +205,96 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                             state,
                             ChordMap::Random,
                             val == ChordMap::Random,
+                            vec![],
+                        );
+
+                        // optimal v1
+                        x1 += 48.0;
+                        x2 += 48.0;
+                        let to_rect = egui::Rect::from_x_y_ranges(x1..=x2, y1..=y2);
+                        let line1 = [Pos2::new(1.0, 1.0), Pos2::new(5.0, 2.0)];
+                        let line2 = [Pos2::new(1.0, 3.0), Pos2::new(5.0, 3.0)];
+                        let line3 = [Pos2::new(1.0, 5.0), Pos2::new(5.0, 4.0)];
+                        let lines = vec![line1, line2, line3];
+                        draw_map_button(
+                            ui,
+                            "",
+                            lines.clone(),
+                            lines.iter().flatten().collect(),
+                            from_rect,
+                            to_rect,
+                            state,
+                            ChordMap::Optimal,
+                            val == ChordMap::Optimal,
+                            vec![OptimalMotion, OptimalCrossings],
+                        );
+
+                        // unison, voices meet in the middle
+                        x1 += 48.0;
+                        x2 += 48.0;
+                        let to_rect = egui::Rect::from_x_y_ranges(x1..=x2, y1..=y2);
+                        let unison = Pos2::new(3.0, 3.0);
+                        let lines: Vec<[Pos2; 2]> = [1.0, 3.0, 5.0]
+                            .iter()
+                            .flat_map(|&y| {
+                                [
+                                    [Pos2::new(1.0, y), unison],
+                                    [unison, Pos2::new(5.0, 6.0 - y)],
+                                ]
+                            })
+                            .collect();
+                        draw_map_button(
+                            ui,
+                            "",
+                            lines.clone(),
+                            lines.iter().flatten().collect(),
+                            from_rect,
+                            to_rect,
+                            state,
+                            ChordMap::Unison,
+                            val == ChordMap::Unison,
+                            vec![UnisonDirection, UnisonPitch, UnisonNote],
                         );
+
+                        // mapping preference, one voice choosing between three target notes
+                        let val =
+                            MappingPreference::from_f32(state.get_parameter(BendMappingPreference));
+                        let targets = [
+                            Pos2::new(5.0, 1.0),
+                            Pos2::new(5.0, 3.0),
+                            Pos2::new(5.0, 5.0),
+                        ];
+                        let preferences = [
+                            (MappingPreference::Closest, Pos2::new(1.0, 4.0), Some(2)),
+                            (MappingPreference::Furthest, Pos2::new(1.0, 4.0), Some(0)),
+                            (MappingPreference::Top, Pos2::new(1.0, 3.0), Some(0)),
+                            (MappingPreference::Center, Pos2::new(1.0, 3.0), Some(1)),
+                            (MappingPreference::Bottom, Pos2::new(1.0, 3.0), Some(2)),
+                            (MappingPreference::Random, Pos2::new(1.0, 3.0), None),
+                        ];
+                        let mut px1 = 92.0;
+                        for (preference, voice, target) in preferences.iter() {
+                            let to_rect =
+                                egui::Rect::from_x_y_ranges(px1..=px1 + 22.0, 80.0..=98.0);
+                            let (text, lines) = match target {
+                                Some(idx) => ("", vec![[*voice, targets[*idx]]]),
+                                None => ("？", vec![]),
+                            };
+                            let mut notes: Vec<&Pos2> = targets.iter().collect();
+                            notes.push(voice);
+                            draw_preference_button(
+                                ui,
+                                text,
+                                lines,
+                                notes,
+                                from_rect,
+                                to_rect,
+                                state,
+                                *preference,
+                                val == *preference,
+                            );
+                            px1 += 28.0;
+                        }
                     });
                 });
 
@@ -177,8 +307,8 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                         let from_rect = egui::Rect::from_x_y_ranges(0.0..=6.0, 0.0..=6.0);
 
                         // S-curve
-                        x1 += 60.0;
-                        x2 += 60.0;
+                        x1 += 48.0;
+                        x2 += 48.0;
                         let to_rect = egui::Rect::from_x_y_ranges(x1..=x2, y1..=y2);
                         let mut keyboard_focus = state.keyboard_focus.lock().unwrap();
                         let p1 = Pos2::new(1.0, 5.0);
@@ -213,8 +343,8 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                         );
 
                         // linear
-                        x1 += 60.0;
-                        x2 += 60.0;
+                        x1 += 48.0;
+                        x2 += 48.0;
                         let to_rect = egui::Rect::from_x_y_ranges(x1..=x2, y1..=y2);
                         let p1 = Pos2::new(1.0, 5.0);
                         let p2 = Pos2::new(5.0, 1.0);
@@ -240,8 +370,8 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                         );
 
                         // sin
-                        x1 += 60.0;
-                        x2 += 60.0;
+                        x1 += 48.0;
+                        x2 += 48.0;
                         let to_rect = egui::Rect::from_x_y_ranges(x1..=x2, y1..=y2);
                         let p1 = Pos2::new(1.0, 5.0);
                         let p2 = Pos2::new(5.0, 1.0);
@@ -277,8 +407,8 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                         );
 
                         // triangle
-                        x1 += 60.0;
-                        x2 += 60.0;
+                        x1 += 48.0;
+                        x2 += 48.0;
                         let to_rect = egui::Rect::from_x_y_ranges(x1..=x2, y1..=y2);
                         let p1 = Pos2::new(1.0, 5.0);
                         let p2 = Pos2::new(5.0, 1.0);
@@ -316,8 +446,8 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                         );
 
                         // saw
-                        x1 += 60.0;
-                        x2 += 60.0;
+                        x1 += 48.0;
+                        x2 += 48.0;
                         let to_rect = egui::Rect::from_x_y_ranges(x1..=x2, y1..=y2);
                         let p1 = Pos2::new(1.0, 5.0);
                         let p2 = Pos2::new(5.0, 1.0);
@@ -355,8 +485,8 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                         );
 
                         // step
-                        x1 += 60.0;
-                        x2 += 60.0;
+                        x1 += 48.0;
+                        x2 += 48.0;
                         let to_rect = egui::Rect::from_x_y_ranges(x1..=x2, y1..=y2);
                         let p1 = Pos2::new(1.0, 5.0);
                         let p2 = Pos2::new(5.0, 1.0);
@@ -394,11 +524,26 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                 // TODO add dark and light mode?
                 //egui::widgets::global_dark_light_mode_switch(ui);
                 ui.vertical(|ui| {
-                    ui.label("Parameters");
+                    ui.horizontal(|ui| {
+                        ui.label("Parameters");
+                        draw_history(ui, state);
+                        ui.separator();
+                        draw_key_range(ui, state);
+                        ui.separator();
+                        let frozen = state.timeline_view.lock().unwrap().frozen_at.is_some();
+                        if ui
+                            .selectable_label(frozen, "Freeze")
+                            .on_hover_text("stop the timeline to inspect it")
+                            .clicked()
+                        {
+                            state.timeline_view.lock().unwrap().toggle_freeze(ui_now);
+                        }
+                    });
                     let parameter_editor_rect = Rect::from_x_y_ranges(630.0..=900.0, 15.0..=150.0);
 
                     let editor_params = state.editor_params.lock().unwrap().to_vec();
                     if editor_params.is_empty() {
+                        ui.horizontal(|ui| draw_preset_folder(ui, state));
                         ui.horizontal(|ui| {
                             if let Err(e) = draw_save_preset(ui, state) {
                                 let mut error_state = state.error_state.lock().unwrap();
@@ -409,6 +554,12 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                             let mut error_state = state.error_state.lock().unwrap();
                             *error_state = Some(ErrorState::new(e.to_string()));
                         };
+                        ui.horizontal(|ui| {
+                            if let Err(e) = draw_manage_preset(ui, state) {
+                                let mut error_state = state.error_state.lock().unwrap();
+                                *error_state = Some(ErrorState::new(e.to_string()));
+                            };
+                        });
                     } else {
                         draw_parameter_editor(ui, state, editor_params, parameter_editor_rect);
                     }
@@ -432,21 +583,73 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
 
             // draw elements
             let mut shapes = vec![];
-            // TODO make this progromatically = BendDuration.max config
-            let history_duration = Duration::from_secs(8);
-            let bend_duration = Duration::from_nanos(chord_bender.bend_duration as u64);
-            let timeline = Timeline::new(timeline_rect, 3..=4, history_duration, bend_duration);
-            let mut timeline_shapes = timeline.draw(ui_now, notes);
+            // scroll to zoom, drag to look back, double click to return to the present
+            let timeline_response = ui.interact(
+                timeline_rect,
+                egui::Id::new("timeline"),
+                egui::Sense::click_and_drag(),
+            );
+            let bend_seconds = state.get_ui_parameter(BendDuration);
+            let (history_duration, view_now, frozen) = {
+                let mut view = state.timeline_view.lock().unwrap();
+                if timeline_response.hovered() {
+                    let scroll_delta = ui.input().scroll_delta;
+                    view.zoom((-scroll_delta.y as f64 / 200.0).exp(), bend_seconds);
+                    let pan = scroll_delta.x + timeline_response.drag_delta().x;
+                    let seconds_per_point =
+                        view.visible_seconds(bend_seconds) / timeline_rect.width() as f64;
+                    view.scroll(pan as f64 * seconds_per_point);
+                }
+                if timeline_response.double_clicked() {
+                    view.seconds = TimelineView::default().seconds;
+                    view.scroll_back = 0.0;
+                }
+                (
+                    Duration::from_secs_f64(view.visible_seconds(bend_seconds)),
+                    view.present(ui_now),
+                    view.frozen_at.is_some(),
+                )
+            };
+            let bend_duration =
+                Duration::from_nanos(state.get_gliss_parameter(BendDuration) as u64);
+            // the last chord's voices and the keys still held
+            let active_notes: Vec<u8> = match engine_view.chords.last() {
+                Some(chord) => chord
+                    .notes
+                    .iter()
+                    .filter(|note| !note.key_released)
+                    .map(|note| note.midi_number)
+                    .collect(),
+                None => vec![],
+            };
+            let mut sounding: Vec<u8> = engine_view
+                .chords
+                .iter()
+                .rev()
+                .find(|chord| chord.sent_to_bender)
+                .map(|chord| chord.voices.iter().map(|voice| voice.midi_number).collect())
+                .unwrap_or_default();
+            sounding.extend(active_notes.iter().copied());
+            let octave_range = state.key_range.lock().unwrap().visible(&sounding);
+            let timeline = Timeline::new(
+                timeline_rect,
+                octave_range.clone(),
+                history_duration,
+                bend_duration,
+            );
+            let mut timeline_shapes = timeline.draw(view_now, notes);
             shapes.append(&mut timeline_shapes);
             timeline.draw_control_pin(state, ui);
             timeline.draw_hold_pin(state, ui);
 
-            let midi_notes = timeline.midi_notes;
+            let midi_notes = timeline.midi_notes.clone();
             let min_midi = midi_notes.clone().min().unwrap() as f32;
             let max_midi = midi_notes.max().unwrap() as f32;
             //let midi_range = min_midi - 0.5..=max_midi + 0.5;
-            let start_time = (ui_now - timeline.history_duration).as_secs_f32();
-            let end_time = (ui_now + timeline.bend_duration).as_secs_f32();
+            let start_time = view_now
+                .saturating_sub(timeline.history_duration)
+                .as_secs_f32();
+            let end_time = (view_now + timeline.bend_duration).as_secs_f32();
             //let time_range = start_time..=end_time;
             // TODO reimplement logic in correct orientation?
             // flipping upsidedown
@@ -456,29 +659,20 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
             );
             let midi_number_x_time_to_screen =
                 emath::RectTransform::from_to(midi_number_x_time, timeline_rect);
-            let mut rendered_benders = state.rendered_benders.lock().unwrap();
-
-            // drop old rendered benders
-            // TODO LOW PRIORITY
-            // TODO do this check less often?
-            // TODO this is not working as expected
-            //rendered_benders.retain(start_time);
-
-            rendered_benders.render(ui, midi_number_x_time_to_screen);
+            engine_view.rendered_benders.render(
+                ui,
+                midi_number_x_time_to_screen,
+                start_time,
+                end_time,
+            );
 
-            let active_notes: Vec<u8> = match chord_bender.chords.last() {
-                Some(chord) => chord
-                    .notes
-                    .iter()
-                    .filter(|note| !note.key_released)
-                    .map(|note| note.midi_number)
-                    .collect(),
-                None => vec![],
-            };
-            let mut piano = piano::draw(piano_rect, 3..=4, active_notes);
+            let mut piano = piano::draw(piano_rect, octave_range, active_notes);
             shapes.append(&mut piano);
 
             ui.painter().extend(shapes);
+            if frozen {
+                timeline.draw_pitch_rulers(ui, ui.input().pointer.hover_pos());
+            }
 
             // inform user of errors
             if let Some(error_state) = &*state.error_state.lock().unwrap() {
@@ -502,6 +696,10 @@ pub fn update() -> impl FnMut(&egui::CtxRef, &mut Queue, &mut Arc<EditorState>)
                 );
             }
         });
+        // whatever was drawn this frame, hosts get end_edit once nothing is held down
+        if !egui_ctx.input().pointer.any_down() {
+            state.end_gesture();
+        }
     }
 }
 
@@ -541,6 +739,8 @@ impl Editor for GlissEditor {
 
     fn close(&mut self) {
         self.is_open = false;
+        // a drag cut off by the window closing never sees its pointer released
+        self.state.end_gesture();
         if let Some(mut window_handle) = self.window_handle.take() {
             window_handle.close();
         }